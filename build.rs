@@ -0,0 +1,4 @@
+fn main() {
+    // `Py_3_11` and `Py_LIMITED_API` decide whether buffers can be exported
+    pyo3_build_config::use_pyo3_cfgs();
+}
@@ -1,50 +1,1075 @@
-from typing import Generic, TypeVar
+import sys
+from typing import Any, Awaitable, Callable, ClassVar, Dict, Generic, Iterator, List, Optional, Tuple, TypeVar
+
+ABORT: object
+
+class Ordering:
+	Relaxed: "Ordering"
+	Acquire: "Ordering"
+	Release: "Ordering"
+	AcqRel: "Ordering"
+	SeqCst: "Ordering"
 
 class AtomicBool:
 	def __init__(self, value: bool):
 		...
-	def load(self) -> bool:
+	@staticmethod
+	def from_buffer(buf: Any, offset: int = 0) -> "AtomicBool":
+		...
+	def load(self, *, ordering: Ordering = Ordering.SeqCst) -> bool:
+		...
+	def store(self, value: bool, *, ordering: Ordering = Ordering.SeqCst) -> bool:
+		...
+	def exchange(self, value: bool, *, ordering: Ordering = Ordering.SeqCst) -> bool:
+		...
+	def compare_exchange(self, expected: bool, desired: bool, *, success: Ordering = Ordering.SeqCst, failure: Optional[Ordering] = None) -> bool:
+		...
+	def compare_exchange_result(self, expected: bool, desired: bool, *, success: Ordering = Ordering.SeqCst, failure: Optional[Ordering] = None) -> Tuple[bool, bool]:
+		...
+	def compare_exchange_weak(self, expected: bool, desired: bool, *, success: Ordering = Ordering.SeqCst, failure: Optional[Ordering] = None) -> Tuple[bool, bool]:
+		...
+	def fetch_and(self, val: bool, *, ordering: Ordering = Ordering.SeqCst) -> bool:
+		...
+	def fetch_or(self, val: bool, *, ordering: Ordering = Ordering.SeqCst) -> bool:
+		...
+	def fetch_xor(self, val: bool, *, ordering: Ordering = Ordering.SeqCst) -> bool:
+		...
+	def fetch_nand(self, val: bool, *, ordering: Ordering = Ordering.SeqCst) -> bool:
+		...
+	def fetch_not(self, *, ordering: Ordering = Ordering.SeqCst) -> bool:
+		...
+	def fetch_update(self, f: Callable[[bool], Any], *, ordering: Ordering = Ordering.SeqCst) -> bool:
+		...
+	def update_and_get(self, f: Callable[[bool], Any], *, ordering: Ordering = Ordering.SeqCst) -> bool:
+		...
+	def wait(self, expected: bool, timeout: Optional[float] = None) -> bool:
 		...
-	def store(self, value: bool) -> bool:
+	def notify_one(self) -> None:
 		...
-	def exchange(self, value: bool) -> bool:
+	def notify_all(self) -> None:
 		...
-	def compare_exchange(self, expected: bool, desired: bool) -> bool:
+	def wait_for_change(self, expected: bool) -> Awaitable[bool]:
+		...
+	def wait_until(self, predicate: Callable[[bool], Any]) -> Awaitable[bool]:
+		...
+	def __bool__(self) -> bool:
+		...
+	def __reduce__(self) -> Tuple[Any, ...]:
+		...
+	def __copy__(self) -> "AtomicBool":
+		...
+	def __deepcopy__(self, memo: Dict[int, Any]) -> "AtomicBool":
 		...
 
 class AtomicInt:
-	def __init__(self, value: int):
+	def __init__(self, value: int, strict: bool = False):
 		...
-	def load(self) -> int:
+	@staticmethod
+	def from_buffer(buf: Any, offset: int = 0, strict: bool = False) -> "AtomicInt":
+		...
+	def load(self, *, ordering: Ordering = Ordering.SeqCst) -> int:
+		...
+	def store(self, value: int, *, ordering: Ordering = Ordering.SeqCst) -> int:
+		...
+	def exchange(self, value: int, *, ordering: Ordering = Ordering.SeqCst) -> int:
+		...
+	def compare_exchange(self, expected: int, desired: int, *, success: Ordering = Ordering.SeqCst, failure: Optional[Ordering] = None) -> int:
+		...
+	def compare_exchange_result(self, expected: int, desired: int, *, success: Ordering = Ordering.SeqCst, failure: Optional[Ordering] = None) -> Tuple[bool, int]:
+		...
+	def compare_exchange_weak(self, expected: int, desired: int, *, success: Ordering = Ordering.SeqCst, failure: Optional[Ordering] = None) -> Tuple[bool, int]:
+		...
+	def fetch_add(self, val: int, *, ordering: Ordering = Ordering.SeqCst) -> int:
+		...
+	def fetch_sub(self, val: int, *, ordering: Ordering = Ordering.SeqCst) -> int:
+		...
+	def fetch_and(self, val: int, *, ordering: Ordering = Ordering.SeqCst) -> int:
+		...
+	def fetch_or(self, val: int, *, ordering: Ordering = Ordering.SeqCst) -> int:
+		...
+	def fetch_xor(self, val: int, *, ordering: Ordering = Ordering.SeqCst) -> int:
+		...
+	def fetch_nand(self, val: int, *, ordering: Ordering = Ordering.SeqCst) -> int:
+		...
+	def fetch_max(self, val: int, *, ordering: Ordering = Ordering.SeqCst) -> int:
+		...
+	def fetch_min(self, val: int, *, ordering: Ordering = Ordering.SeqCst) -> int:
+		...
+	def add_and_fetch(self, val: int, *, ordering: Ordering = Ordering.SeqCst) -> int:
+		...
+	def sub_and_fetch(self, val: int, *, ordering: Ordering = Ordering.SeqCst) -> int:
+		...
+	def and_and_fetch(self, val: int, *, ordering: Ordering = Ordering.SeqCst) -> int:
+		...
+	def or_and_fetch(self, val: int, *, ordering: Ordering = Ordering.SeqCst) -> int:
+		...
+	def xor_and_fetch(self, val: int, *, ordering: Ordering = Ordering.SeqCst) -> int:
+		...
+	def nand_and_fetch(self, val: int, *, ordering: Ordering = Ordering.SeqCst) -> int:
+		...
+	def max_and_fetch(self, val: int, *, ordering: Ordering = Ordering.SeqCst) -> int:
+		...
+	def min_and_fetch(self, val: int, *, ordering: Ordering = Ordering.SeqCst) -> int:
+		...
+	def fetch_update(self, f: Callable[[int], Any], *, ordering: Ordering = Ordering.SeqCst) -> int:
+		...
+	def update_and_get(self, f: Callable[[int], Any], *, ordering: Ordering = Ordering.SeqCst) -> int:
+		...
+	def wait(self, expected: int, timeout: Optional[float] = None) -> bool:
+		...
+	def notify_one(self) -> None:
+		...
+	def notify_all(self) -> None:
+		...
+	def wait_for_change(self, expected: int) -> Awaitable[int]:
+		...
+	def wait_until(self, predicate: Callable[[int], Any]) -> Awaitable[int]:
+		...
+	def __int__(self) -> int:
+		...
+	def __index__(self) -> int:
+		...
+	def __float__(self) -> float:
+		...
+	def __format__(self, spec: str) -> str:
+		...
+	__hash__: ClassVar[None]  # type: ignore[assignment]
+	def __eq__(self, other: object) -> bool:
+		...
+	def __ne__(self, other: object) -> bool:
+		...
+	def __lt__(self, other: object) -> bool:
+		...
+	def __le__(self, other: object) -> bool:
+		...
+	def __gt__(self, other: object) -> bool:
+		...
+	def __ge__(self, other: object) -> bool:
+		...
+	def __add__(self, other: int) -> int:
+		...
+	def __radd__(self, other: int) -> int:
+		...
+	def __sub__(self, other: int) -> int:
+		...
+	def __rsub__(self, other: int) -> int:
+		...
+	def __mul__(self, other: int) -> int:
+		...
+	def __rmul__(self, other: int) -> int:
+		...
+	def __floordiv__(self, other: int) -> int:
+		...
+	def __rfloordiv__(self, other: int) -> int:
+		...
+	def __mod__(self, other: int) -> int:
+		...
+	def __rmod__(self, other: int) -> int:
+		...
+	def __lshift__(self, other: int) -> int:
+		...
+	def __rlshift__(self, other: int) -> int:
+		...
+	def __rshift__(self, other: int) -> int:
+		...
+	def __rrshift__(self, other: int) -> int:
+		...
+	def __and__(self, other: int) -> int:
+		...
+	def __rand__(self, other: int) -> int:
+		...
+	def __or__(self, other: int) -> int:
+		...
+	def __ror__(self, other: int) -> int:
+		...
+	def __xor__(self, other: int) -> int:
+		...
+	def __rxor__(self, other: int) -> int:
+		...
+	def __truediv__(self, other: int) -> float:
+		...
+	def __rtruediv__(self, other: int) -> float:
+		...
+	def __pow__(self, other: int, modulo: Optional[int] = None) -> Any:
+		...
+	def __rpow__(self, other: int, modulo: Optional[int] = None) -> Any:
+		...
+	def __neg__(self) -> int:
+		...
+	def __pos__(self) -> int:
+		...
+	def __abs__(self) -> int:
+		...
+	def __invert__(self) -> int:
+		...
+	def __iadd__(self, other: int) -> "AtomicInt":
+		...
+	def __isub__(self, other: int) -> "AtomicInt":
+		...
+	def __iand__(self, other: int) -> "AtomicInt":
+		...
+	def __ior__(self, other: int) -> "AtomicInt":
+		...
+	def __ixor__(self, other: int) -> "AtomicInt":
+		...
+	def __bool__(self) -> bool:
+		...
+	def __reduce__(self) -> Tuple[Any, ...]:
+		...
+	def __copy__(self) -> "AtomicInt":
+		...
+	def __deepcopy__(self, memo: Dict[int, Any]) -> "AtomicInt":
+		...
+
+class AtomicInt64:
+	def __init__(self, value: int, strict: bool = True):
+		...
+	@staticmethod
+	def from_buffer(buf: Any, offset: int = 0, strict: bool = True) -> "AtomicInt64":
+		...
+	def load(self, *, ordering: Ordering = Ordering.SeqCst) -> int:
+		...
+	def store(self, value: int, *, ordering: Ordering = Ordering.SeqCst) -> int:
+		...
+	def exchange(self, value: int, *, ordering: Ordering = Ordering.SeqCst) -> int:
+		...
+	def compare_exchange(self, expected: int, desired: int, *, success: Ordering = Ordering.SeqCst, failure: Optional[Ordering] = None) -> int:
+		...
+	def compare_exchange_result(self, expected: int, desired: int, *, success: Ordering = Ordering.SeqCst, failure: Optional[Ordering] = None) -> Tuple[bool, int]:
+		...
+	def compare_exchange_weak(self, expected: int, desired: int, *, success: Ordering = Ordering.SeqCst, failure: Optional[Ordering] = None) -> Tuple[bool, int]:
+		...
+	def fetch_add(self, val: int, *, ordering: Ordering = Ordering.SeqCst) -> int:
+		...
+	def fetch_sub(self, val: int, *, ordering: Ordering = Ordering.SeqCst) -> int:
+		...
+	def fetch_and(self, val: int, *, ordering: Ordering = Ordering.SeqCst) -> int:
+		...
+	def fetch_or(self, val: int, *, ordering: Ordering = Ordering.SeqCst) -> int:
+		...
+	def fetch_xor(self, val: int, *, ordering: Ordering = Ordering.SeqCst) -> int:
+		...
+	def fetch_nand(self, val: int, *, ordering: Ordering = Ordering.SeqCst) -> int:
+		...
+	def fetch_max(self, val: int, *, ordering: Ordering = Ordering.SeqCst) -> int:
+		...
+	def fetch_min(self, val: int, *, ordering: Ordering = Ordering.SeqCst) -> int:
+		...
+	def add_and_fetch(self, val: int, *, ordering: Ordering = Ordering.SeqCst) -> int:
+		...
+	def sub_and_fetch(self, val: int, *, ordering: Ordering = Ordering.SeqCst) -> int:
+		...
+	def and_and_fetch(self, val: int, *, ordering: Ordering = Ordering.SeqCst) -> int:
+		...
+	def or_and_fetch(self, val: int, *, ordering: Ordering = Ordering.SeqCst) -> int:
+		...
+	def xor_and_fetch(self, val: int, *, ordering: Ordering = Ordering.SeqCst) -> int:
+		...
+	def nand_and_fetch(self, val: int, *, ordering: Ordering = Ordering.SeqCst) -> int:
+		...
+	def max_and_fetch(self, val: int, *, ordering: Ordering = Ordering.SeqCst) -> int:
+		...
+	def min_and_fetch(self, val: int, *, ordering: Ordering = Ordering.SeqCst) -> int:
+		...
+	def fetch_update(self, f: Callable[[int], Any], *, ordering: Ordering = Ordering.SeqCst) -> int:
+		...
+	def update_and_get(self, f: Callable[[int], Any], *, ordering: Ordering = Ordering.SeqCst) -> int:
 		...
-	def store(self, value: int) -> int:
+	def wait(self, expected: int, timeout: Optional[float] = None) -> bool:
 		...
-	def exchange(self, value: int) -> int:
+	def notify_one(self) -> None:
 		...
-	def compare_exchange(self, expected: int, desired: int) -> int:
+	def notify_all(self) -> None:
 		...
-	def fetch_add(self, val: int) -> int:
+	def wait_for_change(self, expected: int) -> Awaitable[int]:
 		...
-	def fetch_sub(self, val: int) -> int:
+	def wait_until(self, predicate: Callable[[int], Any]) -> Awaitable[int]:
 		...
-	def fetch_and(self, val: int) -> int:
+	def __int__(self) -> int:
 		...
-	def fetch_or(self, val: int) -> int:
+	def __index__(self) -> int:
 		...
-	def fetch_xor(self, val: int) -> int:
+	def __float__(self) -> float:
+		...
+	def __format__(self, spec: str) -> str:
+		...
+	__hash__: ClassVar[None]  # type: ignore[assignment]
+	def __eq__(self, other: object) -> bool:
+		...
+	def __ne__(self, other: object) -> bool:
+		...
+	def __lt__(self, other: object) -> bool:
+		...
+	def __le__(self, other: object) -> bool:
+		...
+	def __gt__(self, other: object) -> bool:
+		...
+	def __ge__(self, other: object) -> bool:
+		...
+	def __add__(self, other: int) -> int:
+		...
+	def __radd__(self, other: int) -> int:
+		...
+	def __sub__(self, other: int) -> int:
+		...
+	def __rsub__(self, other: int) -> int:
+		...
+	def __mul__(self, other: int) -> int:
+		...
+	def __rmul__(self, other: int) -> int:
+		...
+	def __floordiv__(self, other: int) -> int:
+		...
+	def __rfloordiv__(self, other: int) -> int:
+		...
+	def __mod__(self, other: int) -> int:
+		...
+	def __rmod__(self, other: int) -> int:
+		...
+	def __lshift__(self, other: int) -> int:
+		...
+	def __rlshift__(self, other: int) -> int:
+		...
+	def __rshift__(self, other: int) -> int:
+		...
+	def __rrshift__(self, other: int) -> int:
+		...
+	def __and__(self, other: int) -> int:
+		...
+	def __rand__(self, other: int) -> int:
+		...
+	def __or__(self, other: int) -> int:
+		...
+	def __ror__(self, other: int) -> int:
+		...
+	def __xor__(self, other: int) -> int:
+		...
+	def __rxor__(self, other: int) -> int:
+		...
+	def __truediv__(self, other: int) -> float:
+		...
+	def __rtruediv__(self, other: int) -> float:
+		...
+	def __pow__(self, other: int, modulo: Optional[int] = None) -> Any:
+		...
+	def __rpow__(self, other: int, modulo: Optional[int] = None) -> Any:
+		...
+	def __neg__(self) -> int:
+		...
+	def __pos__(self) -> int:
+		...
+	def __abs__(self) -> int:
+		...
+	def __invert__(self) -> int:
+		...
+	def __iadd__(self, other: int) -> "AtomicInt64":
+		...
+	def __isub__(self, other: int) -> "AtomicInt64":
+		...
+	def __iand__(self, other: int) -> "AtomicInt64":
+		...
+	def __ior__(self, other: int) -> "AtomicInt64":
+		...
+	def __ixor__(self, other: int) -> "AtomicInt64":
+		...
+	def __bool__(self) -> bool:
+		...
+	def __reduce__(self) -> Tuple[Any, ...]:
+		...
+	def __copy__(self) -> "AtomicInt64":
+		...
+	def __deepcopy__(self, memo: Dict[int, Any]) -> "AtomicInt64":
+		...
+
+class AtomicUInt32:
+	def __init__(self, value: int, strict: bool = True):
+		...
+	@staticmethod
+	def from_buffer(buf: Any, offset: int = 0, strict: bool = True) -> "AtomicUInt32":
+		...
+	def load(self, *, ordering: Ordering = Ordering.SeqCst) -> int:
+		...
+	def store(self, value: int, *, ordering: Ordering = Ordering.SeqCst) -> int:
+		...
+	def exchange(self, value: int, *, ordering: Ordering = Ordering.SeqCst) -> int:
+		...
+	def compare_exchange(self, expected: int, desired: int, *, success: Ordering = Ordering.SeqCst, failure: Optional[Ordering] = None) -> int:
+		...
+	def compare_exchange_result(self, expected: int, desired: int, *, success: Ordering = Ordering.SeqCst, failure: Optional[Ordering] = None) -> Tuple[bool, int]:
+		...
+	def compare_exchange_weak(self, expected: int, desired: int, *, success: Ordering = Ordering.SeqCst, failure: Optional[Ordering] = None) -> Tuple[bool, int]:
+		...
+	def fetch_add(self, val: int, *, ordering: Ordering = Ordering.SeqCst) -> int:
+		...
+	def fetch_sub(self, val: int, *, ordering: Ordering = Ordering.SeqCst) -> int:
+		...
+	def fetch_and(self, val: int, *, ordering: Ordering = Ordering.SeqCst) -> int:
+		...
+	def fetch_or(self, val: int, *, ordering: Ordering = Ordering.SeqCst) -> int:
+		...
+	def fetch_xor(self, val: int, *, ordering: Ordering = Ordering.SeqCst) -> int:
+		...
+	def fetch_nand(self, val: int, *, ordering: Ordering = Ordering.SeqCst) -> int:
+		...
+	def fetch_max(self, val: int, *, ordering: Ordering = Ordering.SeqCst) -> int:
+		...
+	def fetch_min(self, val: int, *, ordering: Ordering = Ordering.SeqCst) -> int:
+		...
+	def add_and_fetch(self, val: int, *, ordering: Ordering = Ordering.SeqCst) -> int:
+		...
+	def sub_and_fetch(self, val: int, *, ordering: Ordering = Ordering.SeqCst) -> int:
+		...
+	def and_and_fetch(self, val: int, *, ordering: Ordering = Ordering.SeqCst) -> int:
+		...
+	def or_and_fetch(self, val: int, *, ordering: Ordering = Ordering.SeqCst) -> int:
+		...
+	def xor_and_fetch(self, val: int, *, ordering: Ordering = Ordering.SeqCst) -> int:
+		...
+	def nand_and_fetch(self, val: int, *, ordering: Ordering = Ordering.SeqCst) -> int:
+		...
+	def max_and_fetch(self, val: int, *, ordering: Ordering = Ordering.SeqCst) -> int:
+		...
+	def min_and_fetch(self, val: int, *, ordering: Ordering = Ordering.SeqCst) -> int:
+		...
+	def fetch_update(self, f: Callable[[int], Any], *, ordering: Ordering = Ordering.SeqCst) -> int:
+		...
+	def update_and_get(self, f: Callable[[int], Any], *, ordering: Ordering = Ordering.SeqCst) -> int:
+		...
+	def wait(self, expected: int, timeout: Optional[float] = None) -> bool:
+		...
+	def notify_one(self) -> None:
+		...
+	def notify_all(self) -> None:
+		...
+	def wait_for_change(self, expected: int) -> Awaitable[int]:
+		...
+	def wait_until(self, predicate: Callable[[int], Any]) -> Awaitable[int]:
+		...
+	def __int__(self) -> int:
+		...
+	def __index__(self) -> int:
+		...
+	def __float__(self) -> float:
+		...
+	def __format__(self, spec: str) -> str:
+		...
+	__hash__: ClassVar[None]  # type: ignore[assignment]
+	def __eq__(self, other: object) -> bool:
+		...
+	def __ne__(self, other: object) -> bool:
+		...
+	def __lt__(self, other: object) -> bool:
+		...
+	def __le__(self, other: object) -> bool:
+		...
+	def __gt__(self, other: object) -> bool:
+		...
+	def __ge__(self, other: object) -> bool:
+		...
+	def __add__(self, other: int) -> int:
+		...
+	def __radd__(self, other: int) -> int:
+		...
+	def __sub__(self, other: int) -> int:
+		...
+	def __rsub__(self, other: int) -> int:
+		...
+	def __mul__(self, other: int) -> int:
+		...
+	def __rmul__(self, other: int) -> int:
+		...
+	def __floordiv__(self, other: int) -> int:
+		...
+	def __rfloordiv__(self, other: int) -> int:
+		...
+	def __mod__(self, other: int) -> int:
+		...
+	def __rmod__(self, other: int) -> int:
+		...
+	def __lshift__(self, other: int) -> int:
+		...
+	def __rlshift__(self, other: int) -> int:
+		...
+	def __rshift__(self, other: int) -> int:
+		...
+	def __rrshift__(self, other: int) -> int:
+		...
+	def __and__(self, other: int) -> int:
+		...
+	def __rand__(self, other: int) -> int:
+		...
+	def __or__(self, other: int) -> int:
+		...
+	def __ror__(self, other: int) -> int:
+		...
+	def __xor__(self, other: int) -> int:
+		...
+	def __rxor__(self, other: int) -> int:
+		...
+	def __truediv__(self, other: int) -> float:
+		...
+	def __rtruediv__(self, other: int) -> float:
+		...
+	def __pow__(self, other: int, modulo: Optional[int] = None) -> Any:
+		...
+	def __rpow__(self, other: int, modulo: Optional[int] = None) -> Any:
+		...
+	def __neg__(self) -> int:
+		...
+	def __pos__(self) -> int:
+		...
+	def __abs__(self) -> int:
+		...
+	def __invert__(self) -> int:
+		...
+	def __iadd__(self, other: int) -> "AtomicUInt32":
+		...
+	def __isub__(self, other: int) -> "AtomicUInt32":
+		...
+	def __iand__(self, other: int) -> "AtomicUInt32":
+		...
+	def __ior__(self, other: int) -> "AtomicUInt32":
+		...
+	def __ixor__(self, other: int) -> "AtomicUInt32":
+		...
+	def __bool__(self) -> bool:
+		...
+	def __reduce__(self) -> Tuple[Any, ...]:
+		...
+	def __copy__(self) -> "AtomicUInt32":
+		...
+	def __deepcopy__(self, memo: Dict[int, Any]) -> "AtomicUInt32":
+		...
+
+class AtomicUInt64:
+	def __init__(self, value: int, strict: bool = True):
+		...
+	@staticmethod
+	def from_buffer(buf: Any, offset: int = 0, strict: bool = True) -> "AtomicUInt64":
+		...
+	def load(self, *, ordering: Ordering = Ordering.SeqCst) -> int:
+		...
+	def store(self, value: int, *, ordering: Ordering = Ordering.SeqCst) -> int:
+		...
+	def exchange(self, value: int, *, ordering: Ordering = Ordering.SeqCst) -> int:
+		...
+	def compare_exchange(self, expected: int, desired: int, *, success: Ordering = Ordering.SeqCst, failure: Optional[Ordering] = None) -> int:
+		...
+	def compare_exchange_result(self, expected: int, desired: int, *, success: Ordering = Ordering.SeqCst, failure: Optional[Ordering] = None) -> Tuple[bool, int]:
+		...
+	def compare_exchange_weak(self, expected: int, desired: int, *, success: Ordering = Ordering.SeqCst, failure: Optional[Ordering] = None) -> Tuple[bool, int]:
+		...
+	def fetch_add(self, val: int, *, ordering: Ordering = Ordering.SeqCst) -> int:
+		...
+	def fetch_sub(self, val: int, *, ordering: Ordering = Ordering.SeqCst) -> int:
+		...
+	def fetch_and(self, val: int, *, ordering: Ordering = Ordering.SeqCst) -> int:
+		...
+	def fetch_or(self, val: int, *, ordering: Ordering = Ordering.SeqCst) -> int:
+		...
+	def fetch_xor(self, val: int, *, ordering: Ordering = Ordering.SeqCst) -> int:
+		...
+	def fetch_nand(self, val: int, *, ordering: Ordering = Ordering.SeqCst) -> int:
+		...
+	def fetch_max(self, val: int, *, ordering: Ordering = Ordering.SeqCst) -> int:
+		...
+	def fetch_min(self, val: int, *, ordering: Ordering = Ordering.SeqCst) -> int:
+		...
+	def add_and_fetch(self, val: int, *, ordering: Ordering = Ordering.SeqCst) -> int:
+		...
+	def sub_and_fetch(self, val: int, *, ordering: Ordering = Ordering.SeqCst) -> int:
+		...
+	def and_and_fetch(self, val: int, *, ordering: Ordering = Ordering.SeqCst) -> int:
+		...
+	def or_and_fetch(self, val: int, *, ordering: Ordering = Ordering.SeqCst) -> int:
+		...
+	def xor_and_fetch(self, val: int, *, ordering: Ordering = Ordering.SeqCst) -> int:
+		...
+	def nand_and_fetch(self, val: int, *, ordering: Ordering = Ordering.SeqCst) -> int:
+		...
+	def max_and_fetch(self, val: int, *, ordering: Ordering = Ordering.SeqCst) -> int:
+		...
+	def min_and_fetch(self, val: int, *, ordering: Ordering = Ordering.SeqCst) -> int:
+		...
+	def fetch_update(self, f: Callable[[int], Any], *, ordering: Ordering = Ordering.SeqCst) -> int:
+		...
+	def update_and_get(self, f: Callable[[int], Any], *, ordering: Ordering = Ordering.SeqCst) -> int:
+		...
+	def wait(self, expected: int, timeout: Optional[float] = None) -> bool:
+		...
+	def notify_one(self) -> None:
+		...
+	def notify_all(self) -> None:
+		...
+	def wait_for_change(self, expected: int) -> Awaitable[int]:
+		...
+	def wait_until(self, predicate: Callable[[int], Any]) -> Awaitable[int]:
+		...
+	def __int__(self) -> int:
+		...
+	def __index__(self) -> int:
+		...
+	def __float__(self) -> float:
+		...
+	def __format__(self, spec: str) -> str:
+		...
+	__hash__: ClassVar[None]  # type: ignore[assignment]
+	def __eq__(self, other: object) -> bool:
+		...
+	def __ne__(self, other: object) -> bool:
+		...
+	def __lt__(self, other: object) -> bool:
+		...
+	def __le__(self, other: object) -> bool:
+		...
+	def __gt__(self, other: object) -> bool:
+		...
+	def __ge__(self, other: object) -> bool:
+		...
+	def __add__(self, other: int) -> int:
+		...
+	def __radd__(self, other: int) -> int:
+		...
+	def __sub__(self, other: int) -> int:
+		...
+	def __rsub__(self, other: int) -> int:
+		...
+	def __mul__(self, other: int) -> int:
+		...
+	def __rmul__(self, other: int) -> int:
+		...
+	def __floordiv__(self, other: int) -> int:
+		...
+	def __rfloordiv__(self, other: int) -> int:
+		...
+	def __mod__(self, other: int) -> int:
+		...
+	def __rmod__(self, other: int) -> int:
+		...
+	def __lshift__(self, other: int) -> int:
+		...
+	def __rlshift__(self, other: int) -> int:
+		...
+	def __rshift__(self, other: int) -> int:
+		...
+	def __rrshift__(self, other: int) -> int:
+		...
+	def __and__(self, other: int) -> int:
+		...
+	def __rand__(self, other: int) -> int:
+		...
+	def __or__(self, other: int) -> int:
+		...
+	def __ror__(self, other: int) -> int:
+		...
+	def __xor__(self, other: int) -> int:
+		...
+	def __rxor__(self, other: int) -> int:
+		...
+	def __truediv__(self, other: int) -> float:
+		...
+	def __rtruediv__(self, other: int) -> float:
+		...
+	def __pow__(self, other: int, modulo: Optional[int] = None) -> Any:
+		...
+	def __rpow__(self, other: int, modulo: Optional[int] = None) -> Any:
+		...
+	def __neg__(self) -> int:
+		...
+	def __pos__(self) -> int:
+		...
+	def __abs__(self) -> int:
+		...
+	def __invert__(self) -> int:
+		...
+	def __iadd__(self, other: int) -> "AtomicUInt64":
+		...
+	def __isub__(self, other: int) -> "AtomicUInt64":
+		...
+	def __iand__(self, other: int) -> "AtomicUInt64":
+		...
+	def __ior__(self, other: int) -> "AtomicUInt64":
+		...
+	def __ixor__(self, other: int) -> "AtomicUInt64":
+		...
+	def __bool__(self) -> bool:
+		...
+	def __reduce__(self) -> Tuple[Any, ...]:
+		...
+	def __copy__(self) -> "AtomicUInt64":
+		...
+	def __deepcopy__(self, memo: Dict[int, Any]) -> "AtomicUInt64":
+		...
+
+class AtomicFloat:
+	def __init__(self, value: float):
+		...
+	@staticmethod
+	def from_buffer(buf: Any, offset: int = 0) -> "AtomicFloat":
+		...
+	def load(self, *, ordering: Ordering = Ordering.SeqCst) -> float:
+		...
+	def store(self, value: float, *, ordering: Ordering = Ordering.SeqCst) -> float:
+		...
+	def exchange(self, value: float, *, ordering: Ordering = Ordering.SeqCst) -> float:
+		...
+	def compare_exchange(self, expected: float, desired: float, *, success: Ordering = Ordering.SeqCst, failure: Optional[Ordering] = None) -> float:
+		...
+	def compare_exchange_result(self, expected: float, desired: float, *, success: Ordering = Ordering.SeqCst, failure: Optional[Ordering] = None) -> Tuple[bool, float]:
+		...
+	def compare_exchange_weak(self, expected: float, desired: float, *, success: Ordering = Ordering.SeqCst, failure: Optional[Ordering] = None) -> Tuple[bool, float]:
+		...
+	def fetch_add(self, val: float, *, ordering: Ordering = Ordering.SeqCst) -> float:
+		...
+	def fetch_sub(self, val: float, *, ordering: Ordering = Ordering.SeqCst) -> float:
+		...
+	def fetch_max(self, val: float, *, ordering: Ordering = Ordering.SeqCst) -> float:
+		...
+	def fetch_min(self, val: float, *, ordering: Ordering = Ordering.SeqCst) -> float:
+		...
+	def fetch_update(self, f: Callable[[float], Any], *, ordering: Ordering = Ordering.SeqCst) -> float:
+		...
+	def update_and_get(self, f: Callable[[float], Any], *, ordering: Ordering = Ordering.SeqCst) -> float:
+		...
+	def __float__(self) -> float:
+		...
+	def __bool__(self) -> bool:
+		...
+	def __reduce__(self) -> Tuple[Any, ...]:
+		...
+	def __copy__(self) -> "AtomicFloat":
+		...
+	def __deepcopy__(self, memo: Dict[int, Any]) -> "AtomicFloat":
 		...
 
 
 T = TypeVar("T")
+K = TypeVar("K")
+V = TypeVar("V")
 
 class AtomicObject(Generic[T]):
-	def __init__(self, value: T):
+	def __init__(self, value: T, identity: bool = False):
+		...
+	def load(self, *, ordering: Ordering = Ordering.SeqCst) -> T:
+		...
+	def store(self, value: T, *, ordering: Ordering = Ordering.SeqCst):
+		...
+	def exchange(self, value: T, *, ordering: Ordering = Ordering.SeqCst) -> T:
+		...
+	def compare_exchange(self, expected: T, desired: T, *, success: Ordering = Ordering.SeqCst, failure: Optional[Ordering] = None) -> T:
+		...
+	def compare_exchange_result(self, expected: T, desired: T, *, success: Ordering = Ordering.SeqCst, failure: Optional[Ordering] = None) -> Tuple[bool, T]:
+		...
+	def compare_exchange_weak(self, expected: T, desired: T, *, success: Ordering = Ordering.SeqCst, failure: Optional[Ordering] = None) -> Tuple[bool, T]:
+		...
+	def compare_exchange_identity(self, expected: T, desired: T, *, success: Ordering = Ordering.SeqCst, failure: Optional[Ordering] = None) -> T:
+		...
+	def fetch_update(self, f: Callable[[T], Any], *, ordering: Ordering = Ordering.SeqCst) -> T:
+		...
+	def update_and_get(self, f: Callable[[T], Any], *, ordering: Ordering = Ordering.SeqCst) -> T:
+		...
+	def wait_for_change(self, expected: T) -> Awaitable[T]:
+		...
+	def wait_until(self, predicate: Callable[[T], Any]) -> Awaitable[T]:
+		...
+	def __reduce__(self) -> Tuple[Any, ...]:
+		...
+	def __setstate__(self, state: T) -> None:
+		...
+	def __copy__(self) -> "AtomicObject[T]":
+		...
+	def __deepcopy__(self, memo: Dict[int, Any]) -> "AtomicObject[T]":
+		...
+
+class AtomicIntArray:
+	def __init__(self, length: int, initial: int = 0):
+		...
+	def load(self, index: int, *, ordering: Ordering = Ordering.SeqCst) -> int:
+		...
+	def store(self, index: int, value: int, *, ordering: Ordering = Ordering.SeqCst) -> int:
+		...
+	def exchange(self, index: int, value: int, *, ordering: Ordering = Ordering.SeqCst) -> int:
+		...
+	def compare_exchange(self, index: int, expected: int, desired: int, *, success: Ordering = Ordering.SeqCst, failure: Optional[Ordering] = None) -> int:
+		...
+	def compare_exchange_result(self, index: int, expected: int, desired: int, *, success: Ordering = Ordering.SeqCst, failure: Optional[Ordering] = None) -> Tuple[bool, int]:
+		...
+	def compare_exchange_weak(self, index: int, expected: int, desired: int, *, success: Ordering = Ordering.SeqCst, failure: Optional[Ordering] = None) -> Tuple[bool, int]:
+		...
+	def fetch_add(self, index: int, val: int, *, ordering: Ordering = Ordering.SeqCst) -> int:
+		...
+	def fetch_sub(self, index: int, val: int, *, ordering: Ordering = Ordering.SeqCst) -> int:
+		...
+	def fetch_and(self, index: int, val: int, *, ordering: Ordering = Ordering.SeqCst) -> int:
+		...
+	def fetch_or(self, index: int, val: int, *, ordering: Ordering = Ordering.SeqCst) -> int:
+		...
+	def fetch_xor(self, index: int, val: int, *, ordering: Ordering = Ordering.SeqCst) -> int:
 		...
-	def load(self) -> T:
+	def fetch_nand(self, index: int, val: int, *, ordering: Ordering = Ordering.SeqCst) -> int:
+		...
+	def fetch_max(self, index: int, val: int, *, ordering: Ordering = Ordering.SeqCst) -> int:
+		...
+	def fetch_min(self, index: int, val: int, *, ordering: Ordering = Ordering.SeqCst) -> int:
+		...
+	def snapshot(self) -> List[int]:
+		...
+	def __len__(self) -> int:
+		...
+	def __getitem__(self, index: int) -> int:
+		...
+	def __setitem__(self, index: int, value: int) -> None:
+		...
+	@property
+	def __array_interface__(self) -> Dict[str, Any]:
+		...
+	if sys.version_info >= (3, 12):
+		# Only wheels built for python 3.11 and later export buffers
+		def __buffer__(self, flags: int, /) -> memoryview:
+			...
+
+class AtomicObjectArray(Generic[T]):
+	def __init__(self, length: int, fill: T = None, identity: bool = False):
+		...
+	def load(self, index: int, *, ordering: Ordering = Ordering.SeqCst) -> T:
+		...
+	def store(self, index: int, value: T, *, ordering: Ordering = Ordering.SeqCst) -> T:
+		...
+	def exchange(self, index: int, value: T, *, ordering: Ordering = Ordering.SeqCst) -> T:
+		...
+	def compare_exchange(self, index: int, expected: T, desired: T, *, success: Ordering = Ordering.SeqCst, failure: Optional[Ordering] = None) -> T:
+		...
+	def compare_exchange_result(self, index: int, expected: T, desired: T, *, success: Ordering = Ordering.SeqCst, failure: Optional[Ordering] = None) -> Tuple[bool, T]:
+		...
+	def compare_exchange_weak(self, index: int, expected: T, desired: T, *, success: Ordering = Ordering.SeqCst, failure: Optional[Ordering] = None) -> Tuple[bool, T]:
+		...
+	def compare_exchange_identity(self, index: int, expected: T, desired: T, *, success: Ordering = Ordering.SeqCst, failure: Optional[Ordering] = None) -> T:
+		...
+	def fetch_update(self, index: int, f: Callable[[T], Any], *, ordering: Ordering = Ordering.SeqCst) -> T:
+		...
+	def update_and_get(self, index: int, f: Callable[[T], Any], *, ordering: Ordering = Ordering.SeqCst) -> T:
+		...
+	def snapshot(self) -> List[T]:
+		...
+	def __len__(self) -> int:
+		...
+	def __getitem__(self, index: int) -> T:
+		...
+	def __setitem__(self, index: int, value: T) -> None:
+		...
+
+class AtomicBitSet:
+	def __init__(self, nbits: int):
+		...
+	@property
+	def nbits(self) -> int:
+		...
+	def test(self, bit: int, *, ordering: Ordering = Ordering.SeqCst) -> bool:
+		...
+	def set(self, bit: int, *, ordering: Ordering = Ordering.SeqCst) -> None:
+		...
+	def clear(self, bit: int, *, ordering: Ordering = Ordering.SeqCst) -> None:
+		...
+	def test_and_set(self, bit: int, *, ordering: Ordering = Ordering.SeqCst) -> bool:
+		...
+	def test_and_clear(self, bit: int, *, ordering: Ordering = Ordering.SeqCst) -> bool:
+		...
+	def find_first_clear_and_set(self, *, ordering: Ordering = Ordering.SeqCst) -> Optional[int]:
+		...
+	def count(self, *, ordering: Ordering = Ordering.SeqCst) -> int:
+		...
+	def __contains__(self, bit: int) -> bool:
+		...
+	def __iter__(self) -> Iterator[int]:
+		...
+
+class Mutex:
+	def __init__(self):
+		...
+	def acquire(self) -> None:
+		...
+	def try_acquire(self, timeout: float = 0.0) -> bool:
+		...
+	def release(self) -> None:
+		...
+	def __enter__(self) -> "Mutex":
+		...
+	def __exit__(self, exc_type: Any, exc: Any, tb: Any) -> None:
+		...
+
+class RwLockGuard:
+	def __enter__(self) -> None:
+		...
+	def __exit__(self, exc_type: Any, exc: Any, tb: Any) -> None:
+		...
+
+class RwLock:
+	def __init__(self):
+		...
+	def acquire_read(self) -> None:
+		...
+	def try_acquire_read(self, timeout: float = 0.0) -> bool:
+		...
+	def release_read(self) -> None:
+		...
+	def acquire_write(self) -> None:
+		...
+	def try_acquire_write(self, timeout: float = 0.0) -> bool:
+		...
+	def release_write(self) -> None:
+		...
+	def read(self) -> RwLockGuard:
+		...
+	def write(self) -> RwLockGuard:
+		...
+
+class Condition:
+	def __init__(self, mutex: Optional[Mutex] = None):
+		...
+	@property
+	def mutex(self) -> Mutex:
+		...
+	def acquire(self) -> None:
+		...
+	def try_acquire(self, timeout: float = 0.0) -> bool:
+		...
+	def release(self) -> None:
+		...
+	def wait(self, timeout: Optional[float] = None) -> bool:
+		...
+	def signal(self) -> None:
+		...
+	def broadcast(self) -> None:
+		...
+	def __enter__(self) -> "Condition":
+		...
+	def __exit__(self, exc_type: Any, exc: Any, tb: Any) -> None:
+		...
+
+class Semaphore:
+	def __init__(self, value: int = 1):
+		...
+	def acquire(self) -> None:
+		...
+	def try_acquire(self, timeout: float = 0.0) -> bool:
+		...
+	def release(self) -> None:
+		...
+	def __enter__(self) -> "Semaphore":
+		...
+	def __exit__(self, exc_type: Any, exc: Any, tb: Any) -> None:
+		...
+
+class QueueClosed(Exception):
+	...
+
+class ConcurrentQueue(Generic[T]):
+	def __init__(self, capacity: Optional[int] = None):
+		...
+	@property
+	def capacity(self) -> Optional[int]:
+		...
+	@property
+	def closed(self) -> bool:
+		...
+	def push(self, item: T, timeout: Optional[float] = None) -> bool:
+		...
+	def try_push(self, item: T) -> bool:
+		...
+	def try_pop(self, default: Any = None) -> Any:
+		...
+	def pop(self, timeout: Optional[float] = None) -> T:
+		...
+	def close(self) -> None:
+		...
+	def __len__(self) -> int:
+		...
+
+class SpscRing(Generic[T]):
+	def __init__(self, capacity: int):
+		...
+	@property
+	def capacity(self) -> int:
+		...
+	def push(self, item: T) -> bool:
+		...
+	def push_many(self, items: List[T]) -> int:
+		...
+	def pop(self, default: Any = None) -> Any:
+		...
+	def pop_many(self, max_items: Optional[int] = None) -> List[T]:
+		...
+	def __len__(self) -> int:
+		...
+
+class SpscByteRing:
+	def __init__(self, capacity: int, record_size: int):
+		...
+	@property
+	def capacity(self) -> int:
+		...
+	@property
+	def record_size(self) -> int:
+		...
+	def push(self, data: bytes) -> bool:
+		...
+	def push_many(self, data: bytes) -> int:
+		...
+	def pop(self) -> Optional[bytes]:
+		...
+	def pop_many(self, max_records: Optional[int] = None) -> bytes:
+		...
+	def __len__(self) -> int:
+		...
+
+class AtomicStack(Generic[T]):
+	def __init__(self):
+		...
+	def push(self, item: T) -> None:
+		...
+	def pop(self, default: Any = None) -> Any:
+		...
+	def peek(self, default: Any = None) -> Any:
+		...
+	def drain(self) -> List[T]:
+		...
+	def is_empty(self) -> bool:
+		...
+
+class ConcurrentMap(Generic[K, V]):
+	def __init__(self, stripes: int = 16):
+		...
+	def get(self, key: K, default: Any = None) -> Any:
+		...
+	def insert(self, key: K, value: V) -> Optional[V]:
+		...
+	def remove(self, key: K, default: Any = None) -> Any:
+		...
+	def compute_if_absent(self, key: K, func: Callable[[K], V]) -> V:
+		...
+	def compare_and_set(self, key: K, expected: V, new: V) -> bool:
+		...
+	def items(self) -> Iterator[Tuple[K, V]]:
+		...
+	def __len__(self) -> int:
+		...
+	def __contains__(self, key: K) -> bool:
+		...
+	def __iter__(self) -> Iterator[K]:
+		...
+
+class StripedCounter:
+	def __init__(self, cells: Optional[int] = None):
+		...
+	def add(self, val: int) -> None:
+		...
+	def increment(self) -> None:
+		...
+	def load(self) -> int:
 		...
-	def store(self, value: T):
+	def reset(self) -> None:
 		...
-	def exchange(self, value: T) -> T:
+	def sum_then_reset(self) -> int:
 		...
-	def compare_exchange(self, expected: T, desired: T) -> T:
+	def __int__(self) -> int:
 		...
\ No newline at end of file
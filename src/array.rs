@@ -0,0 +1,553 @@
+use crate::{ordering::Ordering, reclaim::Domain, slot::Slot, wrapping_bits};
+#[cfg(any(Py_3_11, not(Py_LIMITED_API)))]
+use pyo3::exceptions::PyBufferError;
+use pyo3::{exceptions::PyIndexError, ffi, prelude::*, types::PyDict, PyTraverseError, PyVisit};
+use std::sync::atomic::{AtomicI32, AtomicPtr, Ordering::SeqCst};
+#[cfg(any(Py_3_11, not(Py_LIMITED_API)))]
+use std::{
+    ffi::{c_int, c_void},
+    mem, ptr,
+};
+
+// How often `snapshot` reads the array again when it changed during a read
+const SNAPSHOT_ATTEMPTS: usize = 8;
+
+#[pyclass(module = "haxe_atomic", frozen)]
+pub struct AtomicIntArray {
+    slots: Box<[AtomicI32]>,
+}
+
+#[pymethods]
+impl AtomicIntArray {
+    #[new]
+    #[pyo3(signature = (length, initial = None))]
+    fn new(length: usize, initial: Option<&Bound<'_, PyAny>>) -> PyResult<Self> {
+        let initial = match initial {
+            Some(initial) => wrapping_bits(initial)? as i32,
+            None => 0,
+        };
+        Ok(Self {
+            slots: (0..length).map(|_| AtomicI32::new(initial)).collect(),
+        })
+    }
+
+    #[pyo3(signature = (index, *, ordering = Ordering::SeqCst))]
+    pub fn load(&self, index: isize, ordering: Ordering) -> PyResult<i32> {
+        Ok(self.slot(index)?.load(ordering.load()?))
+    }
+
+    #[pyo3(signature = (index, value, *, ordering = Ordering::SeqCst))]
+    pub fn store(
+        &self,
+        index: isize,
+        value: &Bound<'_, PyAny>,
+        ordering: Ordering,
+    ) -> PyResult<i32> {
+        let value = wrapping_bits(value)? as i32;
+        self.slot(index)?.store(value, ordering.store()?);
+        Ok(value)
+    }
+
+    #[pyo3(signature = (index, value, *, ordering = Ordering::SeqCst))]
+    pub fn exchange(
+        &self,
+        index: isize,
+        value: &Bound<'_, PyAny>,
+        ordering: Ordering,
+    ) -> PyResult<i32> {
+        Ok(self
+            .slot(index)?
+            .swap(wrapping_bits(value)? as i32, ordering.rmw()))
+    }
+
+    #[pyo3(signature = (index, expected, desired, *, success = Ordering::SeqCst, failure = None))]
+    pub fn compare_exchange(
+        &self,
+        index: isize,
+        expected: &Bound<'_, PyAny>,
+        desired: &Bound<'_, PyAny>,
+        success: Ordering,
+        failure: Option<Ordering>,
+    ) -> PyResult<i32> {
+        Ok(self
+            .compare_exchange_result(index, expected, desired, success, failure)?
+            .1)
+    }
+
+    #[pyo3(signature = (index, expected, desired, *, success = Ordering::SeqCst, failure = None))]
+    pub fn compare_exchange_result(
+        &self,
+        index: isize,
+        expected: &Bound<'_, PyAny>,
+        desired: &Bound<'_, PyAny>,
+        success: Ordering,
+        failure: Option<Ordering>,
+    ) -> PyResult<(bool, i32)> {
+        let slot = self.slot(index)?;
+        let (expected, desired) = (
+            wrapping_bits(expected)? as i32,
+            wrapping_bits(desired)? as i32,
+        );
+        let (success, failure) = Ordering::compare_exchange(success, failure)?;
+        Ok(
+            match slot.compare_exchange(expected, desired, success, failure) {
+                Ok(v) => (true, v),
+                Err(v) => (false, v),
+            },
+        )
+    }
+
+    #[pyo3(signature = (index, expected, desired, *, success = Ordering::SeqCst, failure = None))]
+    pub fn compare_exchange_weak(
+        &self,
+        index: isize,
+        expected: &Bound<'_, PyAny>,
+        desired: &Bound<'_, PyAny>,
+        success: Ordering,
+        failure: Option<Ordering>,
+    ) -> PyResult<(bool, i32)> {
+        let slot = self.slot(index)?;
+        let (expected, desired) = (
+            wrapping_bits(expected)? as i32,
+            wrapping_bits(desired)? as i32,
+        );
+        let (success, failure) = Ordering::compare_exchange(success, failure)?;
+        Ok(
+            match slot.compare_exchange_weak(expected, desired, success, failure) {
+                Ok(v) => (true, v),
+                Err(v) => (false, v),
+            },
+        )
+    }
+
+    #[pyo3(signature = (index, val, *, ordering = Ordering::SeqCst))]
+    pub fn fetch_add(
+        &self,
+        index: isize,
+        val: &Bound<'_, PyAny>,
+        ordering: Ordering,
+    ) -> PyResult<i32> {
+        Ok(self
+            .slot(index)?
+            .fetch_add(wrapping_bits(val)? as i32, ordering.rmw()))
+    }
+
+    #[pyo3(signature = (index, val, *, ordering = Ordering::SeqCst))]
+    pub fn fetch_sub(
+        &self,
+        index: isize,
+        val: &Bound<'_, PyAny>,
+        ordering: Ordering,
+    ) -> PyResult<i32> {
+        Ok(self
+            .slot(index)?
+            .fetch_sub(wrapping_bits(val)? as i32, ordering.rmw()))
+    }
+
+    #[pyo3(signature = (index, val, *, ordering = Ordering::SeqCst))]
+    pub fn fetch_and(
+        &self,
+        index: isize,
+        val: &Bound<'_, PyAny>,
+        ordering: Ordering,
+    ) -> PyResult<i32> {
+        Ok(self
+            .slot(index)?
+            .fetch_and(wrapping_bits(val)? as i32, ordering.rmw()))
+    }
+
+    #[pyo3(signature = (index, val, *, ordering = Ordering::SeqCst))]
+    pub fn fetch_or(
+        &self,
+        index: isize,
+        val: &Bound<'_, PyAny>,
+        ordering: Ordering,
+    ) -> PyResult<i32> {
+        Ok(self
+            .slot(index)?
+            .fetch_or(wrapping_bits(val)? as i32, ordering.rmw()))
+    }
+
+    #[pyo3(signature = (index, val, *, ordering = Ordering::SeqCst))]
+    pub fn fetch_xor(
+        &self,
+        index: isize,
+        val: &Bound<'_, PyAny>,
+        ordering: Ordering,
+    ) -> PyResult<i32> {
+        Ok(self
+            .slot(index)?
+            .fetch_xor(wrapping_bits(val)? as i32, ordering.rmw()))
+    }
+
+    #[pyo3(signature = (index, val, *, ordering = Ordering::SeqCst))]
+    pub fn fetch_nand(
+        &self,
+        index: isize,
+        val: &Bound<'_, PyAny>,
+        ordering: Ordering,
+    ) -> PyResult<i32> {
+        Ok(self
+            .slot(index)?
+            .fetch_nand(wrapping_bits(val)? as i32, ordering.rmw()))
+    }
+
+    #[pyo3(signature = (index, val, *, ordering = Ordering::SeqCst))]
+    pub fn fetch_max(
+        &self,
+        index: isize,
+        val: &Bound<'_, PyAny>,
+        ordering: Ordering,
+    ) -> PyResult<i32> {
+        Ok(self
+            .slot(index)?
+            .fetch_max(wrapping_bits(val)? as i32, ordering.rmw()))
+    }
+
+    #[pyo3(signature = (index, val, *, ordering = Ordering::SeqCst))]
+    pub fn fetch_min(
+        &self,
+        index: isize,
+        val: &Bound<'_, PyAny>,
+        ordering: Ordering,
+    ) -> PyResult<i32> {
+        Ok(self
+            .slot(index)?
+            .fetch_min(wrapping_bits(val)? as i32, ordering.rmw()))
+    }
+
+    /// Copy the values into a list.
+    ///
+    /// The array is read until two reads in a row agree, so that the result is
+    /// a state the array was actually in, giving up after a few attempts while
+    /// it is being written to continuously.
+    pub fn snapshot(&self) -> Vec<i32> {
+        let mut prev = self.read();
+        for _ in 0..SNAPSHOT_ATTEMPTS {
+            let next = self.read();
+            if next == prev {
+                break;
+            }
+            prev = next;
+        }
+        prev
+    }
+
+    fn __len__(&self) -> usize {
+        self.slots.len()
+    }
+
+    fn __getitem__(&self, index: isize) -> PyResult<i32> {
+        Ok(self.slot(index)?.load(SeqCst))
+    }
+
+    fn __setitem__(&self, index: isize, value: &Bound<'_, PyAny>) -> PyResult<()> {
+        self.slot(index)?
+            .store(wrapping_bits(value)? as i32, SeqCst);
+        Ok(())
+    }
+
+    fn __repr__(&self) -> String {
+        format!("AtomicIntArray({:?})", self.read())
+    }
+
+    /// Export the slots as a read-only buffer of native `int`s for `memoryview`
+    /// and `numpy`.
+    ///
+    /// Reads through the buffer are not atomic and can tear while the array is
+    /// written to.
+    #[cfg(any(Py_3_11, not(Py_LIMITED_API)))]
+    unsafe fn __getbuffer__(
+        slf: Bound<'_, Self>,
+        view: *mut ffi::Py_buffer,
+        flags: c_int,
+    ) -> PyResult<()> {
+        if flags & ffi::PyBUF_WRITABLE != 0 {
+            return Err(PyBufferError::new_err(
+                "AtomicIntArray buffers are read-only",
+            ));
+        }
+        let slots = &slf.get().slots;
+        // Shape and strides, freed in `__releasebuffer__`
+        let layout = Box::into_raw(Box::new([
+            slots.len() as ffi::Py_ssize_t,
+            mem::size_of::<i32>() as ffi::Py_ssize_t,
+        ]))
+        .cast::<ffi::Py_ssize_t>();
+        let view = &mut *view;
+        view.buf = slots.as_ptr() as *mut c_void;
+        view.len = mem::size_of_val::<[AtomicI32]>(slots) as ffi::Py_ssize_t;
+        view.readonly = 1;
+        view.itemsize = mem::size_of::<i32>() as ffi::Py_ssize_t;
+        view.format = if flags & ffi::PyBUF_FORMAT != 0 {
+            c"i".as_ptr().cast_mut()
+        } else {
+            ptr::null_mut()
+        };
+        view.ndim = 1;
+        view.shape = if flags & ffi::PyBUF_ND != 0 {
+            layout
+        } else {
+            ptr::null_mut()
+        };
+        view.strides = if flags & ffi::PyBUF_STRIDES == ffi::PyBUF_STRIDES {
+            layout.add(1)
+        } else {
+            ptr::null_mut()
+        };
+        view.suboffsets = ptr::null_mut();
+        view.internal = layout.cast();
+        view.obj = slf.into_any().into_ptr();
+        Ok(())
+    }
+
+    #[cfg(any(Py_3_11, not(Py_LIMITED_API)))]
+    unsafe fn __releasebuffer__(&self, view: *mut ffi::Py_buffer) {
+        drop(Box::from_raw(
+            (*view).internal.cast::<[ffi::Py_ssize_t; 2]>(),
+        ));
+    }
+
+    /// Read-only view of the slots for `numpy.asarray`, which keeps the array
+    /// alive through the view's `base`.
+    ///
+    /// Kept for builds that cannot export buffers, since the limited API only
+    /// supports them from python 3.11 on. Reads through the view can tear like
+    /// reads through the buffer.
+    #[getter]
+    fn __array_interface__<'py>(&self, py: Python<'py>) -> PyResult<Bound<'py, PyDict>> {
+        let interface = PyDict::new(py);
+        interface.set_item("version", 3)?;
+        interface.set_item("shape", (self.slots.len(),))?;
+        interface.set_item(
+            "typestr",
+            if cfg!(target_endian = "little") {
+                "<i4"
+            } else {
+                ">i4"
+            },
+        )?;
+        interface.set_item("data", (self.slots.as_ptr() as usize, true))?;
+        Ok(interface)
+    }
+}
+
+impl AtomicIntArray {
+    fn slot(&self, index: isize) -> PyResult<&AtomicI32> {
+        Ok(&self.slots[resolve(index, self.slots.len())?])
+    }
+
+    fn read(&self) -> Vec<i32> {
+        self.slots.iter().map(|slot| slot.load(SeqCst)).collect()
+    }
+}
+
+#[pyclass(module = "haxe_atomic", frozen)]
+#[derive(Debug)]
+pub struct AtomicObjectArray {
+    // Invariant: every slot contains an owned pointer to a valid python object,
+    // or null once cleared
+    slots: Box<[AtomicPtr<ffi::PyObject>]>,
+    // Owned pointers removed from any slot must be released through `domain`
+    domain: Domain,
+    // Compare objects by identity instead of `__eq__` in `compare_exchange`
+    identity: bool,
+}
+
+#[pymethods]
+impl AtomicObjectArray {
+    #[new]
+    #[pyo3(signature = (length, fill = None, identity = false))]
+    fn new(py: Python<'_>, length: usize, fill: Option<Py<PyAny>>, identity: bool) -> Self {
+        let fill = fill.unwrap_or_else(|| py.None());
+        Self {
+            slots: (0..length)
+                .map(|_| AtomicPtr::new(fill.clone_ref(py).into_ptr()))
+                .collect(),
+            domain: Domain::new(),
+            identity,
+        }
+    }
+
+    // Slots are always accessed with `SeqCst` like in `AtomicObject`
+
+    #[pyo3(signature = (index, *, ordering = Ordering::SeqCst))]
+    pub fn load<'py>(
+        &self,
+        py: Python<'py>,
+        index: isize,
+        ordering: Ordering,
+    ) -> PyResult<Bound<'py, PyAny>> {
+        ordering.load()?;
+        Ok(self.slot(index)?.load(py))
+    }
+
+    #[pyo3(signature = (index, value, *, ordering = Ordering::SeqCst))]
+    pub fn store<'py>(
+        &self,
+        index: isize,
+        value: Bound<'py, PyAny>,
+        ordering: Ordering,
+    ) -> PyResult<Bound<'py, PyAny>> {
+        ordering.store()?;
+        self.slot(index)?.swap(value.clone());
+        Ok(value)
+    }
+
+    #[pyo3(signature = (index, value, *, ordering = Ordering::SeqCst))]
+    #[allow(unused_variables)]
+    pub fn exchange<'py>(
+        &self,
+        index: isize,
+        value: Bound<'py, PyAny>,
+        ordering: Ordering,
+    ) -> PyResult<Bound<'py, PyAny>> {
+        Ok(self.slot(index)?.swap(value))
+    }
+
+    #[pyo3(signature = (index, expected, desired, *, success = Ordering::SeqCst, failure = None))]
+    pub fn compare_exchange<'py>(
+        &self,
+        index: isize,
+        expected: &Bound<'py, PyAny>,
+        desired: &Bound<'py, PyAny>,
+        success: Ordering,
+        failure: Option<Ordering>,
+    ) -> PyResult<Bound<'py, PyAny>> {
+        Ok(self
+            .compare_exchange_result(index, expected, desired, success, failure)?
+            .1)
+    }
+
+    #[pyo3(signature = (index, expected, desired, *, success = Ordering::SeqCst, failure = None))]
+    pub fn compare_exchange_result<'py>(
+        &self,
+        index: isize,
+        expected: &Bound<'py, PyAny>,
+        desired: &Bound<'py, PyAny>,
+        success: Ordering,
+        failure: Option<Ordering>,
+    ) -> PyResult<(bool, Bound<'py, PyAny>)> {
+        Ordering::compare_exchange(success, failure)?;
+        self.slot(index)?
+            .compare_exchange(expected, desired, self.identity, false)
+    }
+
+    #[pyo3(signature = (index, expected, desired, *, success = Ordering::SeqCst, failure = None))]
+    pub fn compare_exchange_weak<'py>(
+        &self,
+        index: isize,
+        expected: &Bound<'py, PyAny>,
+        desired: &Bound<'py, PyAny>,
+        success: Ordering,
+        failure: Option<Ordering>,
+    ) -> PyResult<(bool, Bound<'py, PyAny>)> {
+        Ordering::compare_exchange(success, failure)?;
+        self.slot(index)?
+            .compare_exchange(expected, desired, self.identity, true)
+    }
+
+    #[pyo3(signature = (index, expected, desired, *, success = Ordering::SeqCst, failure = None))]
+    pub fn compare_exchange_identity<'py>(
+        &self,
+        index: isize,
+        expected: &Bound<'py, PyAny>,
+        desired: &Bound<'py, PyAny>,
+        success: Ordering,
+        failure: Option<Ordering>,
+    ) -> PyResult<Bound<'py, PyAny>> {
+        Ordering::compare_exchange(success, failure)?;
+        Ok(self
+            .slot(index)?
+            .compare_exchange(expected, desired, true, false)?
+            .1)
+    }
+
+    #[pyo3(signature = (index, f, *, ordering = Ordering::SeqCst))]
+    #[allow(unused_variables)]
+    pub fn fetch_update<'py>(
+        &self,
+        index: isize,
+        f: &Bound<'py, PyAny>,
+        ordering: Ordering,
+    ) -> PyResult<Bound<'py, PyAny>> {
+        Ok(self.slot(index)?.fetch_update(f)?.0)
+    }
+
+    #[pyo3(signature = (index, f, *, ordering = Ordering::SeqCst))]
+    #[allow(unused_variables)]
+    pub fn update_and_get<'py>(
+        &self,
+        index: isize,
+        f: &Bound<'py, PyAny>,
+        ordering: Ordering,
+    ) -> PyResult<Bound<'py, PyAny>> {
+        Ok(self.slot(index)?.fetch_update(f)?.1)
+    }
+
+    /// Copy the objects into a list, each slot being read once in order.
+    pub fn snapshot<'py>(&self, py: Python<'py>) -> Vec<Bound<'py, PyAny>> {
+        (0..self.slots.len())
+            .map(|i| self.slot_at(i).load(py))
+            .collect()
+    }
+
+    fn __len__(&self) -> usize {
+        self.slots.len()
+    }
+
+    fn __getitem__<'py>(&self, py: Python<'py>, index: isize) -> PyResult<Bound<'py, PyAny>> {
+        Ok(self.slot(index)?.load(py))
+    }
+
+    fn __setitem__(&self, index: isize, val: Bound<'_, PyAny>) -> PyResult<()> {
+        self.slot(index)?.swap(val);
+        Ok(())
+    }
+
+    fn __traverse__(&self, visit: PyVisit<'_>) -> Result<(), PyTraverseError> {
+        for i in 0..self.slots.len() {
+            self.slot_at(i).traverse(&visit)?;
+        }
+        Ok(())
+    }
+
+    fn __clear__(&self) {
+        for i in 0..self.slots.len() {
+            self.slot_at(i).clear();
+        }
+    }
+}
+
+impl AtomicObjectArray {
+    fn slot(&self, index: isize) -> PyResult<Slot<'_>> {
+        Ok(self.slot_at(resolve(index, self.slots.len())?))
+    }
+
+    fn slot_at(&self, i: usize) -> Slot<'_> {
+        // Safety: every slot upholds the slot invariant and is only released through `self.domain`
+        unsafe { Slot::new(&self.slots[i], &self.domain, None) }
+    }
+}
+
+impl Drop for AtomicObjectArray {
+    fn drop(&mut self) {
+        for slot in self.slots.iter_mut() {
+            // Safety: nothing else can access the slots anymore, and the GIL is held
+            unsafe { ffi::Py_DecRef(*slot.get_mut()) };
+        }
+    }
+}
+
+// Resolve a possibly negative index like a list does
+fn resolve(index: isize, len: usize) -> PyResult<usize> {
+    let resolved = if index < 0 {
+        index.checked_add_unsigned(len)
+    } else {
+        Some(index)
+    };
+    match resolved {
+        Some(i) if i >= 0 && (i as usize) < len => Ok(i as usize),
+        _ => Err(PyIndexError::new_err("index out of range")),
+    }
+}
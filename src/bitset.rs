@@ -0,0 +1,155 @@
+use crate::{ordering::Ordering, AtomicU64};
+use pyo3::{exceptions::PyIndexError, prelude::*};
+use std::sync::atomic::Ordering::SeqCst;
+
+const WORD_BITS: usize = u64::BITS as usize;
+
+#[pyclass(module = "haxe_atomic", frozen)]
+pub struct AtomicBitSet {
+    words: Box<[AtomicU64]>,
+    nbits: usize,
+}
+
+#[pymethods]
+impl AtomicBitSet {
+    #[new]
+    fn new(nbits: usize) -> Self {
+        Self {
+            words: (0..nbits.div_ceil(WORD_BITS))
+                .map(|_| AtomicU64::new(0))
+                .collect(),
+            nbits,
+        }
+    }
+
+    #[getter]
+    fn nbits(&self) -> usize {
+        self.nbits
+    }
+
+    #[pyo3(signature = (bit, *, ordering = Ordering::SeqCst))]
+    pub fn test(&self, bit: usize, ordering: Ordering) -> PyResult<bool> {
+        let (word, mask) = self.locate(bit)?;
+        Ok(word.load(ordering.load()?) & mask != 0)
+    }
+
+    #[pyo3(signature = (bit, *, ordering = Ordering::SeqCst))]
+    pub fn set(&self, bit: usize, ordering: Ordering) -> PyResult<()> {
+        self.test_and_set(bit, ordering)?;
+        Ok(())
+    }
+
+    #[pyo3(signature = (bit, *, ordering = Ordering::SeqCst))]
+    pub fn clear(&self, bit: usize, ordering: Ordering) -> PyResult<()> {
+        self.test_and_clear(bit, ordering)?;
+        Ok(())
+    }
+
+    /// Set `bit`, returning whether it was already set.
+    #[pyo3(signature = (bit, *, ordering = Ordering::SeqCst))]
+    pub fn test_and_set(&self, bit: usize, ordering: Ordering) -> PyResult<bool> {
+        let (word, mask) = self.locate(bit)?;
+        Ok(word.fetch_or(mask, ordering.rmw()) & mask != 0)
+    }
+
+    /// Clear `bit`, returning whether it was set.
+    #[pyo3(signature = (bit, *, ordering = Ordering::SeqCst))]
+    pub fn test_and_clear(&self, bit: usize, ordering: Ordering) -> PyResult<bool> {
+        let (word, mask) = self.locate(bit)?;
+        Ok(word.fetch_and(!mask, ordering.rmw()) & mask != 0)
+    }
+
+    /// Set the lowest clear bit and return its index, or `None` if every bit is set.
+    ///
+    /// Concurrent callers never claim the same bit, but a bit cleared behind the
+    /// scan may be missed.
+    #[pyo3(signature = (*, ordering = Ordering::SeqCst))]
+    pub fn find_first_clear_and_set(&self, ordering: Ordering) -> Option<usize> {
+        let ordering = ordering.rmw();
+        for (i, word) in self.words.iter().enumerate() {
+            let valid = self.valid_bits(i);
+            let mut current = word.load(SeqCst);
+            while current & valid != valid {
+                let mask = 1 << (!current & valid).trailing_zeros();
+                current = word.fetch_or(mask, ordering);
+                if current & mask == 0 {
+                    return Some(i * WORD_BITS + mask.trailing_zeros() as usize);
+                }
+                // Another thread claimed the bit first
+            }
+        }
+        None
+    }
+
+    /// Number of set bits, each word being read once.
+    #[pyo3(signature = (*, ordering = Ordering::SeqCst))]
+    pub fn count(&self, ordering: Ordering) -> PyResult<usize> {
+        let ordering = ordering.load()?;
+        Ok(self
+            .words
+            .iter()
+            .map(|word| word.load(ordering).count_ones() as usize)
+            .sum())
+    }
+
+    fn __contains__(&self, bit: usize) -> bool {
+        self.locate(bit)
+            .is_ok_and(|(word, mask)| word.load(SeqCst) & mask != 0)
+    }
+
+    // Set bits in ascending order, reading each word when the iterator reaches it
+    fn __iter__(slf: Bound<'_, Self>) -> BitSetIter {
+        BitSetIter {
+            set: slf.unbind(),
+            word: 0,
+            remaining: 0,
+        }
+    }
+}
+
+impl AtomicBitSet {
+    fn locate(&self, bit: usize) -> PyResult<(&AtomicU64, u64)> {
+        if bit >= self.nbits {
+            return Err(PyIndexError::new_err("bit index out of range"));
+        }
+        Ok((&self.words[bit / WORD_BITS], 1 << (bit % WORD_BITS)))
+    }
+
+    // Mask of the bits of word `i` that are part of the set
+    fn valid_bits(&self, i: usize) -> u64 {
+        let bits = self.nbits - i * WORD_BITS;
+        if bits >= WORD_BITS {
+            u64::MAX
+        } else {
+            (1 << bits) - 1
+        }
+    }
+}
+
+#[pyclass(module = "haxe_atomic")]
+pub struct BitSetIter {
+    set: Py<AtomicBitSet>,
+    // Index of the next word to read
+    word: usize,
+    // Set bits of the last word read that have not been returned yet
+    remaining: u64,
+}
+
+#[pymethods]
+impl BitSetIter {
+    fn __iter__(slf: PyRef<'_, Self>) -> PyRef<'_, Self> {
+        slf
+    }
+
+    fn __next__(&mut self) -> Option<usize> {
+        let set = self.set.get();
+        while self.remaining == 0 {
+            let word = set.words.get(self.word)?;
+            self.remaining = word.load(SeqCst);
+            self.word += 1;
+        }
+        let bit = self.remaining.trailing_zeros() as usize;
+        self.remaining &= self.remaining - 1;
+        Some((self.word - 1) * WORD_BITS + bit)
+    }
+}
@@ -0,0 +1,85 @@
+//! A 64-bit counter spread over cells on separate cache lines.
+//!
+//! Each thread adds to the cell picked by an index it is given when it first
+//! touches any counter, so threads only contend when there are more of them than
+//! cells. Reading sums the cells one after another, and is therefore only exact
+//! when no thread is adding at the same time.
+
+use crate::{padded::CachePadded, AtomicI64};
+use pyo3::{exceptions::PyValueError, prelude::*};
+use std::{
+    sync::atomic::{AtomicUsize, Ordering::Relaxed},
+    thread,
+};
+
+static NEXT_INDEX: AtomicUsize = AtomicUsize::new(0);
+
+thread_local! {
+    static INDEX: usize = NEXT_INDEX.fetch_add(1, Relaxed);
+}
+
+/// A counter for many threads adding to it and occasional reads, wrapping around
+/// like `AtomicInt64`.
+#[pyclass(module = "haxe_atomic", frozen)]
+#[derive(Debug)]
+pub struct StripedCounter {
+    cells: Box<[CachePadded<AtomicI64>]>,
+}
+
+#[pymethods]
+impl StripedCounter {
+    /// `cells` defaults to the number of threads that can run in parallel.
+    #[new]
+    #[pyo3(signature = (cells = None))]
+    fn new(cells: Option<usize>) -> PyResult<Self> {
+        let cells = match cells {
+            Some(0) => return Err(PyValueError::new_err("cells must be positive")),
+            Some(cells) => cells,
+            None => thread::available_parallelism().map_or(1, usize::from),
+        };
+        Ok(Self {
+            cells: (0..cells).map(|_| CachePadded(AtomicI64::new(0))).collect(),
+        })
+    }
+
+    pub fn add(&self, val: &Bound<'_, PyAny>) -> PyResult<()> {
+        let val = crate::wrapping_bits(val)? as i64;
+        self.cell().fetch_add(val, Relaxed);
+        Ok(())
+    }
+
+    pub fn increment(&self) {
+        self.cell().fetch_add(1, Relaxed);
+    }
+
+    pub fn load(&self) -> i64 {
+        self.cells
+            .iter()
+            .fold(0, |sum, cell| sum.wrapping_add(cell.load(Relaxed)))
+    }
+
+    /// Set the counter to zero. Additions made during the reset may be lost.
+    pub fn reset(&self) {
+        for cell in self.cells.iter() {
+            cell.store(0, Relaxed);
+        }
+    }
+
+    /// Set the counter to zero and return its previous value. Unlike `reset`,
+    /// every addition is counted either in the result or after it.
+    pub fn sum_then_reset(&self) -> i64 {
+        self.cells
+            .iter()
+            .fold(0, |sum, cell| sum.wrapping_add(cell.swap(0, Relaxed)))
+    }
+
+    fn __int__(&self) -> i64 {
+        self.load()
+    }
+}
+
+impl StripedCounter {
+    fn cell(&self) -> &AtomicI64 {
+        INDEX.with(|index| &self.cells[index % self.cells.len()])
+    }
+}
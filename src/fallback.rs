@@ -0,0 +1,107 @@
+//! Lock-based stand-ins for the 64-bit atomics on targets without native support.
+//!
+//! They mirror the subset of the `std::sync::atomic` API used by this crate,
+//! every operation being sequentially consistent regardless of the ordering passed.
+
+use std::sync::{atomic::Ordering, Mutex, MutexGuard, PoisonError};
+
+macro_rules! locked_atomic {
+    ($name:ident, $int:ty) => {
+        #[derive(Debug, Default)]
+        pub struct $name {
+            value: Mutex<$int>,
+        }
+
+        impl $name {
+            pub const fn new(val: $int) -> Self {
+                Self {
+                    value: Mutex::new(val),
+                }
+            }
+
+            fn lock(&self) -> MutexGuard<'_, $int> {
+                self.value.lock().unwrap_or_else(PoisonError::into_inner)
+            }
+
+            fn update(&self, f: impl FnOnce($int) -> $int) -> $int {
+                let mut value = self.lock();
+                let prev = *value;
+                *value = f(prev);
+                prev
+            }
+
+            pub fn load(&self, _order: Ordering) -> $int {
+                *self.lock()
+            }
+
+            pub fn store(&self, val: $int, _order: Ordering) {
+                *self.lock() = val;
+            }
+
+            pub fn swap(&self, val: $int, _order: Ordering) -> $int {
+                self.update(|_| val)
+            }
+
+            pub fn compare_exchange(
+                &self,
+                current: $int,
+                new: $int,
+                _success: Ordering,
+                _failure: Ordering,
+            ) -> Result<$int, $int> {
+                let mut value = self.lock();
+                if *value == current {
+                    *value = new;
+                    Ok(current)
+                } else {
+                    Err(*value)
+                }
+            }
+
+            pub fn compare_exchange_weak(
+                &self,
+                current: $int,
+                new: $int,
+                success: Ordering,
+                failure: Ordering,
+            ) -> Result<$int, $int> {
+                self.compare_exchange(current, new, success, failure)
+            }
+
+            pub fn fetch_add(&self, val: $int, _order: Ordering) -> $int {
+                self.update(|v| v.wrapping_add(val))
+            }
+
+            pub fn fetch_sub(&self, val: $int, _order: Ordering) -> $int {
+                self.update(|v| v.wrapping_sub(val))
+            }
+
+            pub fn fetch_and(&self, val: $int, _order: Ordering) -> $int {
+                self.update(|v| v & val)
+            }
+
+            pub fn fetch_or(&self, val: $int, _order: Ordering) -> $int {
+                self.update(|v| v | val)
+            }
+
+            pub fn fetch_xor(&self, val: $int, _order: Ordering) -> $int {
+                self.update(|v| v ^ val)
+            }
+
+            pub fn fetch_nand(&self, val: $int, _order: Ordering) -> $int {
+                self.update(|v| !(v & val))
+            }
+
+            pub fn fetch_max(&self, val: $int, _order: Ordering) -> $int {
+                self.update(|v| v.max(val))
+            }
+
+            pub fn fetch_min(&self, val: $int, _order: Ordering) -> $int {
+                self.update(|v| v.min(val))
+            }
+        }
+    };
+}
+
+locked_atomic!(AtomicI64, i64);
+locked_atomic!(AtomicU64, u64);
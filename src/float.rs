@@ -0,0 +1,216 @@
+use crate::{
+    is_abort,
+    ordering::Ordering,
+    shared::{FromBuffer, Storage},
+    AtomicU64,
+};
+use pyo3::{
+    prelude::*,
+    types::{PyFloat, PyType},
+};
+use std::sync::atomic::Ordering::{Relaxed, SeqCst};
+
+// Stored as the bits of an `f64`, arithmetic is done in compare-exchange loops
+#[pyclass(module = "haxe_atomic", frozen)]
+pub struct AtomicFloat {
+    inner: Storage<AtomicU64>,
+}
+
+#[pymethods]
+impl AtomicFloat {
+    #[new]
+    fn new(val: f64) -> Self {
+        Self {
+            inner: Storage::Inline(AtomicU64::new(val.to_bits())),
+        }
+    }
+
+    /// View the native-endian double at `offset` in the writable buffer `buf`
+    /// as an atomic, `offset` being aligned to 8 bytes. The atomic cannot be
+    /// copied or pickled.
+    #[staticmethod]
+    #[pyo3(signature = (buf, offset = 0))]
+    fn from_buffer(buf: &Bound<'_, PyAny>, offset: usize) -> PyResult<Self> {
+        Ok(Self {
+            inner: AtomicU64::from_buffer(buf, offset)?,
+        })
+    }
+
+    #[pyo3(signature = (*, ordering = Ordering::SeqCst))]
+    pub fn load(&self, ordering: Ordering) -> PyResult<f64> {
+        Ok(f64::from_bits(self.inner.load(ordering.load()?)))
+    }
+
+    #[pyo3(signature = (value, *, ordering = Ordering::SeqCst))]
+    pub fn store(&self, value: f64, ordering: Ordering) -> PyResult<f64> {
+        self.inner.store(value.to_bits(), ordering.store()?);
+        Ok(value)
+    }
+
+    #[pyo3(signature = (value, *, ordering = Ordering::SeqCst))]
+    pub fn exchange(&self, value: f64, ordering: Ordering) -> f64 {
+        f64::from_bits(self.inner.swap(value.to_bits(), ordering.rmw()))
+    }
+
+    /// Replace the value with `desired` if it is bitwise identical to `expected`.
+    ///
+    /// Unlike `==`, this means a NaN matches a NaN with the same payload, and
+    /// `0.0` and `-0.0` do not match each other.
+    #[pyo3(signature = (expected, desired, *, success = Ordering::SeqCst, failure = None))]
+    pub fn compare_exchange(
+        &self,
+        expected: f64,
+        desired: f64,
+        success: Ordering,
+        failure: Option<Ordering>,
+    ) -> PyResult<f64> {
+        Ok(self
+            .compare_exchange_result(expected, desired, success, failure)?
+            .1)
+    }
+
+    #[pyo3(signature = (expected, desired, *, success = Ordering::SeqCst, failure = None))]
+    pub fn compare_exchange_result(
+        &self,
+        expected: f64,
+        desired: f64,
+        success: Ordering,
+        failure: Option<Ordering>,
+    ) -> PyResult<(bool, f64)> {
+        let (success, failure) = Ordering::compare_exchange(success, failure)?;
+        Ok(
+            match self.inner.compare_exchange(
+                expected.to_bits(),
+                desired.to_bits(),
+                success,
+                failure,
+            ) {
+                Ok(v) => (true, f64::from_bits(v)),
+                Err(v) => (false, f64::from_bits(v)),
+            },
+        )
+    }
+
+    #[pyo3(signature = (expected, desired, *, success = Ordering::SeqCst, failure = None))]
+    pub fn compare_exchange_weak(
+        &self,
+        expected: f64,
+        desired: f64,
+        success: Ordering,
+        failure: Option<Ordering>,
+    ) -> PyResult<(bool, f64)> {
+        let (success, failure) = Ordering::compare_exchange(success, failure)?;
+        Ok(
+            match self.inner.compare_exchange_weak(
+                expected.to_bits(),
+                desired.to_bits(),
+                success,
+                failure,
+            ) {
+                Ok(v) => (true, f64::from_bits(v)),
+                Err(v) => (false, f64::from_bits(v)),
+            },
+        )
+    }
+
+    #[pyo3(signature = (val, *, ordering = Ordering::SeqCst))]
+    pub fn fetch_add(&self, val: f64, ordering: Ordering) -> PyResult<f64> {
+        Ok(self.update(ordering, |v| Ok(Some(v + val)))?.0)
+    }
+
+    #[pyo3(signature = (val, *, ordering = Ordering::SeqCst))]
+    pub fn fetch_sub(&self, val: f64, ordering: Ordering) -> PyResult<f64> {
+        Ok(self.update(ordering, |v| Ok(Some(v - val)))?.0)
+    }
+
+    /// Replace the value with the maximum of it and `val`.
+    ///
+    /// A NaN on either side is ignored, like `math.fmax` in C.
+    #[pyo3(signature = (val, *, ordering = Ordering::SeqCst))]
+    pub fn fetch_max(&self, val: f64, ordering: Ordering) -> PyResult<f64> {
+        Ok(self.update(ordering, |v| Ok(Some(v.max(val))))?.0)
+    }
+
+    /// Replace the value with the minimum of it and `val`.
+    ///
+    /// A NaN on either side is ignored, like `math.fmin` in C.
+    #[pyo3(signature = (val, *, ordering = Ordering::SeqCst))]
+    pub fn fetch_min(&self, val: f64, ordering: Ordering) -> PyResult<f64> {
+        Ok(self.update(ordering, |v| Ok(Some(v.min(val))))?.0)
+    }
+
+    #[pyo3(signature = (f, *, ordering = Ordering::SeqCst))]
+    pub fn fetch_update(&self, f: &Bound<'_, PyAny>, ordering: Ordering) -> PyResult<f64> {
+        Ok(self.update(ordering, |v| call(f, v))?.0)
+    }
+
+    #[pyo3(signature = (f, *, ordering = Ordering::SeqCst))]
+    pub fn update_and_get(&self, f: &Bound<'_, PyAny>, ordering: Ordering) -> PyResult<f64> {
+        Ok(self.update(ordering, |v| call(f, v))?.1)
+    }
+
+    fn __float__(&self) -> f64 {
+        f64::from_bits(self.inner.load(SeqCst))
+    }
+
+    fn __repr__(&self, py: Python<'_>) -> PyResult<String> {
+        Ok(format!("AtomicFloat({})", self.__str__(py)?))
+    }
+
+    fn __str__(&self, py: Python<'_>) -> PyResult<String> {
+        let val = f64::from_bits(self.inner.load(Relaxed));
+        PyFloat::new(py, val).repr()?.extract()
+    }
+
+    fn __bool__(&self) -> bool {
+        f64::from_bits(self.inner.load(Relaxed)) != 0.0
+    }
+
+    fn __reduce__<'py>(slf: &Bound<'py, Self>) -> PyResult<(Bound<'py, PyType>, (f64,))> {
+        let this = slf.get();
+        this.inner.check_detachable()?;
+        Ok((slf.get_type(), (this.__float__(),)))
+    }
+
+    fn __copy__(&self) -> PyResult<Self> {
+        self.inner.check_detachable()?;
+        Ok(Self::new(self.__float__()))
+    }
+
+    fn __deepcopy__(&self, _memo: &Bound<'_, PyAny>) -> PyResult<Self> {
+        self.__copy__()
+    }
+}
+
+impl AtomicFloat {
+    // Apply `f` until its result can be stored without another thread modifying
+    // the value in between, or until it returns `None`
+    fn update(
+        &self,
+        ordering: Ordering,
+        mut f: impl FnMut(f64) -> PyResult<Option<f64>>,
+    ) -> PyResult<(f64, f64)> {
+        let (set, fetch) = Ordering::compare_exchange(ordering, None)?;
+        let mut prev = self.inner.load(fetch);
+        loop {
+            let Some(next) = f(f64::from_bits(prev))? else {
+                return Ok((f64::from_bits(prev), f64::from_bits(prev)));
+            };
+            match self
+                .inner
+                .compare_exchange_weak(prev, next.to_bits(), set, fetch)
+            {
+                Ok(_) => return Ok((f64::from_bits(prev), next)),
+                Err(cur) => prev = cur,
+            }
+        }
+    }
+}
+
+fn call(f: &Bound<'_, PyAny>, val: f64) -> PyResult<Option<f64>> {
+    let next = f.call1((val,))?;
+    if is_abort(&next)? {
+        return Ok(None);
+    }
+    next.extract().map(Some)
+}
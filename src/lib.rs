@@ -1,199 +1,1299 @@
-use pyo3::{prelude::*, types::PyBool, PyTraverseError, PyVisit};
-use std::sync::atomic::{self, AtomicPtr, Ordering};
+use pyo3::{
+    prelude::*,
+    pyclass::CompareOp,
+    sync::GILOnceCell,
+    types::{PyBool, PyType},
+    PyTraverseError, PyVisit,
+};
+#[cfg(target_has_atomic = "64")]
+use std::sync::atomic::{AtomicI64, AtomicU64};
+use std::sync::{
+    atomic::{
+        self, AtomicPtr, AtomicU8,
+        Ordering::{Relaxed, SeqCst},
+    },
+    Arc,
+};
+
+mod array;
+mod bitset;
+mod counter;
+#[cfg(not(target_has_atomic = "64"))]
+mod fallback;
+mod float;
+mod map;
+mod ordering;
+mod padded;
+mod queue;
+mod reclaim;
+mod shared;
+mod slot;
+mod spsc;
+mod stack;
+mod sync;
+mod wait;
+mod watch;
+
+use array::{AtomicIntArray, AtomicObjectArray};
+use bitset::AtomicBitSet;
+use counter::StripedCounter;
+#[cfg(not(target_has_atomic = "64"))]
+use fallback::{AtomicI64, AtomicU64};
+use float::AtomicFloat;
+use map::ConcurrentMap;
+use ordering::Ordering;
+use queue::{ConcurrentQueue, QueueClosed};
+use reclaim::Domain;
+use shared::{FromBuffer, Storage};
+use slot::Slot;
+use spsc::{SpscByteRing, SpscRing};
+use stack::AtomicStack;
+use sync::{Condition as HaxeCondition, Mutex, RwLock, RwLockGuard, Semaphore};
+use wait::Notifier;
+use watch::{Condition, Watchers};
+
+// Returned from a `fetch_update` callback to leave the value unchanged
+static ABORT: GILOnceCell<Py<PyAny>> = GILOnceCell::new();
+
+fn abort(py: Python<'_>) -> PyResult<&Py<PyAny>> {
+    ABORT.get_or_try_init(py, || {
+        Ok::<_, PyErr>(py.get_type::<PyAny>().call0()?.unbind())
+    })
+}
+
+fn is_abort(val: &Bound<'_, PyAny>) -> PyResult<bool> {
+    Ok(val.is(abort(val.py())?))
+}
 
 #[pymodule(gil_used = false)]
 fn haxe_atomic(m: &Bound<'_, PyModule>) -> PyResult<()> {
+    m.add_class::<Ordering>()?;
     m.add_class::<AtomicBool>()?;
     m.add_class::<AtomicInt>()?;
+    m.add_class::<AtomicInt64>()?;
+    m.add_class::<AtomicUInt32>()?;
+    m.add_class::<AtomicUInt64>()?;
+    m.add_class::<AtomicFloat>()?;
     m.add_class::<AtomicObject>()?;
+    m.add_class::<AtomicIntArray>()?;
+    m.add_class::<AtomicObjectArray>()?;
+    m.add_class::<AtomicBitSet>()?;
+    m.add_class::<Mutex>()?;
+    m.add_class::<RwLock>()?;
+    m.add_class::<RwLockGuard>()?;
+    m.add_class::<HaxeCondition>()?;
+    m.add_class::<Semaphore>()?;
+    m.add_class::<ConcurrentQueue>()?;
+    m.add_class::<SpscRing>()?;
+    m.add_class::<SpscByteRing>()?;
+    m.add_class::<AtomicStack>()?;
+    m.add_class::<ConcurrentMap>()?;
+    m.add_class::<StripedCounter>()?;
+    m.add("QueueClosed", m.py().get_type::<QueueClosed>())?;
+    m.add("ABORT", abort(m.py())?)?;
     Ok(())
 }
 
 #[pyclass(module = "haxe_atomic", frozen)]
 pub struct AtomicBool {
-    inner: atomic::AtomicBool,
+    // A byte rather than an `atomic::AtomicBool`, since a buffer shared with other
+    // processes may hold any value. Nonzero bytes are true, and writes store 0 or 1
+    inner: Storage<AtomicU8>,
+    notifier: Notifier,
+    watchers: Arc<Watchers>,
 }
 
 #[pymethods]
 impl AtomicBool {
     #[new]
     fn new(val: Bound<PyBool>) -> PyResult<Self> {
-        Ok(Self {
-            inner: atomic::AtomicBool::new(val.extract()?),
-        })
+        Ok(Self::with_value(val.extract()?))
     }
 
-    pub fn load(&self) -> bool {
-        self.inner.load(Ordering::SeqCst)
+    /// View the byte at `offset` in the writable buffer `buf` as an atomic bool,
+    /// any nonzero byte being true.
+    ///
+    /// Writes from other processes are not notified, `wait` polls for them and
+    /// `wait_for_change` and `wait_until` do not see them at all. The atomic
+    /// cannot be copied or pickled, since the copy would not share the buffer.
+    #[staticmethod]
+    #[pyo3(signature = (buf, offset = 0))]
+    fn from_buffer(buf: &Bound<'_, PyAny>, offset: usize) -> PyResult<Self> {
+        Ok(Self::with_storage(AtomicU8::from_buffer(buf, offset)?))
     }
 
-    pub fn store(&self, val: bool) -> bool {
-        self.inner.store(val, Ordering::SeqCst);
-        val
+    #[pyo3(signature = (*, ordering = Ordering::SeqCst))]
+    pub fn load(&self, ordering: Ordering) -> PyResult<bool> {
+        Ok(self.get(ordering.load()?))
     }
 
-    pub fn exchange(&self, val: bool) -> bool {
-        self.inner.swap(val, Ordering::SeqCst)
+    #[pyo3(signature = (val, *, ordering = Ordering::SeqCst))]
+    pub fn store(&self, py: Python<'_>, val: bool, ordering: Ordering) -> PyResult<bool> {
+        self.inner.store(val.into(), ordering.store()?);
+        self.watchers.wake(py, ordering.rmw());
+        Ok(val)
     }
 
-    pub fn compare_exchange(&self, current: bool, new: bool) -> bool {
-        match self
-            .inner
-            .compare_exchange(current, new, Ordering::SeqCst, Ordering::SeqCst)
-        {
-            Ok(v) => v,
-            Err(v) => v,
+    #[pyo3(signature = (val, *, ordering = Ordering::SeqCst))]
+    pub fn exchange(&self, py: Python<'_>, val: bool, ordering: Ordering) -> bool {
+        let prev = self.inner.swap(val.into(), ordering.rmw());
+        self.watchers.wake(py, ordering.rmw());
+        prev != 0
+    }
+
+    #[pyo3(signature = (expected, desired, *, success = Ordering::SeqCst, failure = None))]
+    pub fn compare_exchange(
+        &self,
+        py: Python<'_>,
+        expected: bool,
+        desired: bool,
+        success: Ordering,
+        failure: Option<Ordering>,
+    ) -> PyResult<bool> {
+        Ok(self
+            .compare_exchange_result(py, expected, desired, success, failure)?
+            .1)
+    }
+
+    #[pyo3(signature = (expected, desired, *, success = Ordering::SeqCst, failure = None))]
+    pub fn compare_exchange_result(
+        &self,
+        py: Python<'_>,
+        expected: bool,
+        desired: bool,
+        success: Ordering,
+        failure: Option<Ordering>,
+    ) -> PyResult<(bool, bool)> {
+        self.compare_exchange_impl(py, expected, desired, success, failure, false)
+    }
+
+    #[pyo3(signature = (expected, desired, *, success = Ordering::SeqCst, failure = None))]
+    pub fn compare_exchange_weak(
+        &self,
+        py: Python<'_>,
+        expected: bool,
+        desired: bool,
+        success: Ordering,
+        failure: Option<Ordering>,
+    ) -> PyResult<(bool, bool)> {
+        self.compare_exchange_impl(py, expected, desired, success, failure, true)
+    }
+
+    #[pyo3(signature = (val, *, ordering = Ordering::SeqCst))]
+    pub fn fetch_and(&self, py: Python<'_>, val: bool, ordering: Ordering) -> PyResult<bool> {
+        self.modify(py, ordering, |prev| prev & val)
+    }
+
+    #[pyo3(signature = (val, *, ordering = Ordering::SeqCst))]
+    pub fn fetch_or(&self, py: Python<'_>, val: bool, ordering: Ordering) -> PyResult<bool> {
+        self.modify(py, ordering, |prev| prev | val)
+    }
+
+    #[pyo3(signature = (val, *, ordering = Ordering::SeqCst))]
+    pub fn fetch_xor(&self, py: Python<'_>, val: bool, ordering: Ordering) -> PyResult<bool> {
+        self.modify(py, ordering, |prev| prev ^ val)
+    }
+
+    #[pyo3(signature = (val, *, ordering = Ordering::SeqCst))]
+    pub fn fetch_nand(&self, py: Python<'_>, val: bool, ordering: Ordering) -> PyResult<bool> {
+        self.modify(py, ordering, |prev| !(prev & val))
+    }
+
+    #[pyo3(signature = (*, ordering = Ordering::SeqCst))]
+    pub fn fetch_not(&self, py: Python<'_>, ordering: Ordering) -> PyResult<bool> {
+        self.modify(py, ordering, |prev| !prev)
+    }
+
+    #[pyo3(signature = (f, *, ordering = Ordering::SeqCst))]
+    pub fn fetch_update(&self, f: &Bound<'_, PyAny>, ordering: Ordering) -> PyResult<bool> {
+        Ok(self.fetch_update_impl(f, ordering)?.0)
+    }
+
+    #[pyo3(signature = (f, *, ordering = Ordering::SeqCst))]
+    pub fn update_and_get(&self, f: &Bound<'_, PyAny>, ordering: Ordering) -> PyResult<bool> {
+        Ok(self.fetch_update_impl(f, ordering)?.1)
+    }
+
+    #[pyo3(signature = (expected, timeout = None))]
+    pub fn wait(&self, py: Python<'_>, expected: bool, timeout: Option<f64>) -> PyResult<bool> {
+        self.notifier
+            .wait(py, timeout, || self.get(SeqCst) == expected)
+    }
+
+    pub fn notify_one(&self) {
+        self.notifier.notify_one();
+    }
+
+    pub fn notify_all(&self) {
+        self.notifier.notify_all();
+    }
+
+    pub fn wait_for_change<'py>(
+        slf: &Bound<'py, Self>,
+        expected: bool,
+    ) -> PyResult<Bound<'py, PyAny>> {
+        let expected = PyBool::new(slf.py(), expected)
+            .to_owned()
+            .into_any()
+            .unbind();
+        watch::wait(
+            slf.as_any(),
+            &slf.get().watchers,
+            Condition::Changed {
+                expected,
+                identity: false,
+            },
+        )
+    }
+
+    pub fn wait_until<'py>(
+        slf: &Bound<'py, Self>,
+        predicate: Py<PyAny>,
+    ) -> PyResult<Bound<'py, PyAny>> {
+        watch::wait(
+            slf.as_any(),
+            &slf.get().watchers,
+            Condition::Until(predicate),
+        )
+    }
+
+    fn __repr__(&self) -> String {
+        format!("AtomicBool({})", self.__str__())
+    }
+
+    fn __str__(&self) -> &'static str {
+        if self.__bool__() {
+            "True"
+        } else {
+            "False"
         }
     }
-}
-#[pyclass(module = "haxe_atomic", frozen)]
-pub struct AtomicInt {
-    inner: atomic::AtomicI32,
-}
 
-#[pymethods]
-impl AtomicInt {
-    #[new]
-    fn new(val: i32) -> PyResult<Self> {
-        Ok(Self {
-            inner: atomic::AtomicI32::new(val),
-        })
+    fn __bool__(&self) -> bool {
+        self.get(Relaxed)
     }
 
-    pub fn load(&self) -> i32 {
-        self.inner.load(Ordering::SeqCst)
+    fn __reduce__<'py>(slf: &Bound<'py, Self>) -> PyResult<(Bound<'py, PyType>, (bool,))> {
+        let this = slf.get();
+        this.inner.check_detachable()?;
+        Ok((slf.get_type(), (this.get(SeqCst),)))
     }
 
-    pub fn store(&self, val: i32) -> i32 {
-        self.inner.store(val, Ordering::SeqCst);
-        val
+    fn __copy__(&self) -> PyResult<Self> {
+        self.inner.check_detachable()?;
+        Ok(Self::with_value(self.get(SeqCst)))
     }
 
-    pub fn exchange(&self, val: i32) -> i32 {
-        self.inner.swap(val, Ordering::SeqCst)
+    fn __deepcopy__(&self, _memo: &Bound<'_, PyAny>) -> PyResult<Self> {
+        self.__copy__()
     }
 
-    pub fn compare_exchange(&self, current: i32, new: i32) -> i32 {
-        match self
-            .inner
-            .compare_exchange(current, new, Ordering::SeqCst, Ordering::SeqCst)
-        {
-            Ok(v) => v,
-            Err(v) => v,
+    fn __traverse__(&self, visit: PyVisit<'_>) -> Result<(), PyTraverseError> {
+        self.watchers.traverse(&visit)
+    }
+
+    fn __clear__(&self) {
+        self.watchers.clear();
+    }
+}
+
+impl AtomicBool {
+    fn with_value(val: bool) -> Self {
+        Self::with_storage(Storage::Inline(AtomicU8::new(val.into())))
+    }
+
+    fn with_storage(inner: Storage<AtomicU8>) -> Self {
+        Self {
+            inner,
+            notifier: Notifier::new(),
+            watchers: Watchers::new(),
         }
     }
 
-    pub fn fetch_add(&self, val: i32) -> i32 {
-        self.inner.fetch_add(val, Ordering::SeqCst)
+    fn get(&self, ordering: atomic::Ordering) -> bool {
+        self.inner.load(ordering) != 0
     }
 
-    pub fn fetch_sub(&self, val: i32) -> i32 {
-        self.inner.fetch_sub(val, Ordering::SeqCst)
+    fn compare_exchange_impl(
+        &self,
+        py: Python<'_>,
+        current: bool,
+        new: bool,
+        success: Ordering,
+        failure: Option<Ordering>,
+        weak: bool,
+    ) -> PyResult<(bool, bool)> {
+        let (success, failure) = Ordering::compare_exchange(success, failure)?;
+        let mut expected = u8::from(current);
+        loop {
+            let res = if weak {
+                self.inner
+                    .compare_exchange_weak(expected, new.into(), success, failure)
+            } else {
+                self.inner
+                    .compare_exchange(expected, new.into(), success, failure)
+            };
+            match res {
+                Ok(_) => {
+                    self.watchers.wake(py, success);
+                    return Ok((true, current));
+                }
+                // Another nonzero byte is just as true
+                Err(actual) if actual != expected && (actual != 0) == current => expected = actual,
+                Err(actual) => return Ok((false, actual != 0)),
+            }
+        }
     }
 
-    pub fn fetch_and(&self, val: i32) -> i32 {
-        self.inner.fetch_and(val, Ordering::SeqCst)
+    // Replace the value with `f(value)`, returning the previous value
+    fn modify(
+        &self,
+        py: Python<'_>,
+        ordering: Ordering,
+        f: impl Fn(bool) -> bool,
+    ) -> PyResult<bool> {
+        let (set, fetch) = Ordering::compare_exchange(ordering, None)?;
+        let prev = self
+            .inner
+            .fetch_update(set, fetch, |prev| Some(f(prev != 0).into()))
+            .unwrap_or_else(|prev| prev);
+        self.watchers.wake(py, set);
+        Ok(prev != 0)
     }
 
-    pub fn fetch_or(&self, val: i32) -> i32 {
-        self.inner.fetch_or(val, Ordering::SeqCst)
+    fn fetch_update_impl(
+        &self,
+        f: &Bound<'_, PyAny>,
+        ordering: Ordering,
+    ) -> PyResult<(bool, bool)> {
+        let (set, fetch) = Ordering::compare_exchange(ordering, None)?;
+        let mut prev = self.inner.load(fetch);
+        loop {
+            let next = f.call1((prev != 0,))?;
+            if is_abort(&next)? {
+                return Ok((prev != 0, prev != 0));
+            }
+            let next: bool = next.extract()?;
+            match self
+                .inner
+                .compare_exchange_weak(prev, next.into(), set, fetch)
+            {
+                Ok(_) => {
+                    self.watchers.wake(f.py(), set);
+                    return Ok((prev != 0, next));
+                }
+                Err(cur) => prev = cur,
+            }
+        }
     }
+}
+
+macro_rules! atomic_int {
+    ($name:ident, $atomic:ty, $int:ty, strict = $strict:literal) => {
+        #[pyclass(module = "haxe_atomic", frozen)]
+        pub struct $name {
+            inner: Storage<$atomic>,
+            // Raise `OverflowError` for out of range arguments instead of wrapping them
+            strict: bool,
+            notifier: Notifier,
+            watchers: Arc<Watchers>,
+        }
+
+        #[pymethods]
+        impl $name {
+            #[new]
+            #[pyo3(signature = (val, strict = $strict))]
+            fn new(val: &Bound<'_, PyAny>, strict: bool) -> PyResult<Self> {
+                let val = if strict {
+                    val.extract()?
+                } else {
+                    wrapping_bits(val)? as $int
+                };
+                Ok(Self::with_value(val, strict))
+            }
+
+            /// View the native-endian integer at `offset` in the writable buffer
+            /// `buf` as an atomic, `offset` being aligned to its size.
+            ///
+            /// As with `AtomicBool.from_buffer`, writes from other processes are
+            /// only noticed by polling and the atomic cannot be copied or pickled.
+            #[staticmethod]
+            #[pyo3(signature = (buf, offset = 0, strict = $strict))]
+            fn from_buffer(buf: &Bound<'_, PyAny>, offset: usize, strict: bool) -> PyResult<Self> {
+                Ok(Self::with_storage(
+                    <$atomic>::from_buffer(buf, offset)?,
+                    strict,
+                ))
+            }
+
+            #[pyo3(signature = (*, ordering = Ordering::SeqCst))]
+            pub fn load(&self, ordering: Ordering) -> PyResult<$int> {
+                Ok(self.inner.load(ordering.load()?))
+            }
+
+            #[pyo3(signature = (val, *, ordering = Ordering::SeqCst))]
+            pub fn store(&self, val: &Bound<'_, PyAny>, ordering: Ordering) -> PyResult<$int> {
+                let py = val.py();
+                let val = self.extract(val)?;
+                self.inner.store(val, ordering.store()?);
+                self.watchers.wake(py, ordering.rmw());
+                Ok(val)
+            }
+
+            #[pyo3(signature = (val, *, ordering = Ordering::SeqCst))]
+            pub fn exchange(&self, val: &Bound<'_, PyAny>, ordering: Ordering) -> PyResult<$int> {
+                let prev = self.inner.swap(self.extract(val)?, ordering.rmw());
+                self.watchers.wake(val.py(), ordering.rmw());
+                Ok(prev)
+            }
+
+            #[pyo3(signature = (expected, desired, *, success = Ordering::SeqCst, failure = None))]
+            pub fn compare_exchange(
+                &self,
+                expected: &Bound<'_, PyAny>,
+                desired: &Bound<'_, PyAny>,
+                success: Ordering,
+                failure: Option<Ordering>,
+            ) -> PyResult<$int> {
+                Ok(self
+                    .compare_exchange_result(expected, desired, success, failure)?
+                    .1)
+            }
+
+            #[pyo3(signature = (expected, desired, *, success = Ordering::SeqCst, failure = None))]
+            pub fn compare_exchange_result(
+                &self,
+                expected: &Bound<'_, PyAny>,
+                desired: &Bound<'_, PyAny>,
+                success: Ordering,
+                failure: Option<Ordering>,
+            ) -> PyResult<(bool, $int)> {
+                let py = expected.py();
+                let (expected, desired) = (self.extract(expected)?, self.extract(desired)?);
+                let (success, failure) = Ordering::compare_exchange(success, failure)?;
+                Ok(
+                    match self
+                        .inner
+                        .compare_exchange(expected, desired, success, failure)
+                    {
+                        Ok(v) => {
+                            self.watchers.wake(py, success);
+                            (true, v)
+                        }
+                        Err(v) => (false, v),
+                    },
+                )
+            }
+
+            #[pyo3(signature = (expected, desired, *, success = Ordering::SeqCst, failure = None))]
+            pub fn compare_exchange_weak(
+                &self,
+                expected: &Bound<'_, PyAny>,
+                desired: &Bound<'_, PyAny>,
+                success: Ordering,
+                failure: Option<Ordering>,
+            ) -> PyResult<(bool, $int)> {
+                let py = expected.py();
+                let (expected, desired) = (self.extract(expected)?, self.extract(desired)?);
+                let (success, failure) = Ordering::compare_exchange(success, failure)?;
+                Ok(
+                    match self
+                        .inner
+                        .compare_exchange_weak(expected, desired, success, failure)
+                    {
+                        Ok(v) => {
+                            self.watchers.wake(py, success);
+                            (true, v)
+                        }
+                        Err(v) => (false, v),
+                    },
+                )
+            }
+
+            #[pyo3(signature = (val, *, ordering = Ordering::SeqCst))]
+            pub fn fetch_add(&self, val: &Bound<'_, PyAny>, ordering: Ordering) -> PyResult<$int> {
+                let prev = self.inner.fetch_add(self.extract(val)?, ordering.rmw());
+                self.watchers.wake(val.py(), ordering.rmw());
+                Ok(prev)
+            }
+
+            #[pyo3(signature = (val, *, ordering = Ordering::SeqCst))]
+            pub fn fetch_sub(&self, val: &Bound<'_, PyAny>, ordering: Ordering) -> PyResult<$int> {
+                let prev = self.inner.fetch_sub(self.extract(val)?, ordering.rmw());
+                self.watchers.wake(val.py(), ordering.rmw());
+                Ok(prev)
+            }
+
+            #[pyo3(signature = (val, *, ordering = Ordering::SeqCst))]
+            pub fn fetch_and(&self, val: &Bound<'_, PyAny>, ordering: Ordering) -> PyResult<$int> {
+                let prev = self.inner.fetch_and(self.extract(val)?, ordering.rmw());
+                self.watchers.wake(val.py(), ordering.rmw());
+                Ok(prev)
+            }
+
+            #[pyo3(signature = (val, *, ordering = Ordering::SeqCst))]
+            pub fn fetch_or(&self, val: &Bound<'_, PyAny>, ordering: Ordering) -> PyResult<$int> {
+                let prev = self.inner.fetch_or(self.extract(val)?, ordering.rmw());
+                self.watchers.wake(val.py(), ordering.rmw());
+                Ok(prev)
+            }
+
+            #[pyo3(signature = (val, *, ordering = Ordering::SeqCst))]
+            pub fn fetch_xor(&self, val: &Bound<'_, PyAny>, ordering: Ordering) -> PyResult<$int> {
+                let prev = self.inner.fetch_xor(self.extract(val)?, ordering.rmw());
+                self.watchers.wake(val.py(), ordering.rmw());
+                Ok(prev)
+            }
+
+            #[pyo3(signature = (val, *, ordering = Ordering::SeqCst))]
+            pub fn fetch_nand(&self, val: &Bound<'_, PyAny>, ordering: Ordering) -> PyResult<$int> {
+                let prev = self.inner.fetch_nand(self.extract(val)?, ordering.rmw());
+                self.watchers.wake(val.py(), ordering.rmw());
+                Ok(prev)
+            }
+
+            #[pyo3(signature = (val, *, ordering = Ordering::SeqCst))]
+            pub fn fetch_max(&self, val: &Bound<'_, PyAny>, ordering: Ordering) -> PyResult<$int> {
+                let prev = self.inner.fetch_max(self.extract(val)?, ordering.rmw());
+                self.watchers.wake(val.py(), ordering.rmw());
+                Ok(prev)
+            }
+
+            #[pyo3(signature = (val, *, ordering = Ordering::SeqCst))]
+            pub fn fetch_min(&self, val: &Bound<'_, PyAny>, ordering: Ordering) -> PyResult<$int> {
+                let prev = self.inner.fetch_min(self.extract(val)?, ordering.rmw());
+                self.watchers.wake(val.py(), ordering.rmw());
+                Ok(prev)
+            }
+
+            #[pyo3(signature = (val, *, ordering = Ordering::SeqCst))]
+            pub fn add_and_fetch(
+                &self,
+                val: &Bound<'_, PyAny>,
+                ordering: Ordering,
+            ) -> PyResult<$int> {
+                let py = val.py();
+                let val = self.extract(val)?;
+                let prev = self.inner.fetch_add(val, ordering.rmw());
+                self.watchers.wake(py, ordering.rmw());
+                Ok(prev.wrapping_add(val))
+            }
+
+            #[pyo3(signature = (val, *, ordering = Ordering::SeqCst))]
+            pub fn sub_and_fetch(
+                &self,
+                val: &Bound<'_, PyAny>,
+                ordering: Ordering,
+            ) -> PyResult<$int> {
+                let py = val.py();
+                let val = self.extract(val)?;
+                let prev = self.inner.fetch_sub(val, ordering.rmw());
+                self.watchers.wake(py, ordering.rmw());
+                Ok(prev.wrapping_sub(val))
+            }
+
+            #[pyo3(signature = (val, *, ordering = Ordering::SeqCst))]
+            pub fn and_and_fetch(
+                &self,
+                val: &Bound<'_, PyAny>,
+                ordering: Ordering,
+            ) -> PyResult<$int> {
+                let py = val.py();
+                let val = self.extract(val)?;
+                let prev = self.inner.fetch_and(val, ordering.rmw());
+                self.watchers.wake(py, ordering.rmw());
+                Ok(prev & val)
+            }
+
+            #[pyo3(signature = (val, *, ordering = Ordering::SeqCst))]
+            pub fn or_and_fetch(
+                &self,
+                val: &Bound<'_, PyAny>,
+                ordering: Ordering,
+            ) -> PyResult<$int> {
+                let py = val.py();
+                let val = self.extract(val)?;
+                let prev = self.inner.fetch_or(val, ordering.rmw());
+                self.watchers.wake(py, ordering.rmw());
+                Ok(prev | val)
+            }
+
+            #[pyo3(signature = (val, *, ordering = Ordering::SeqCst))]
+            pub fn xor_and_fetch(
+                &self,
+                val: &Bound<'_, PyAny>,
+                ordering: Ordering,
+            ) -> PyResult<$int> {
+                let py = val.py();
+                let val = self.extract(val)?;
+                let prev = self.inner.fetch_xor(val, ordering.rmw());
+                self.watchers.wake(py, ordering.rmw());
+                Ok(prev ^ val)
+            }
+
+            #[pyo3(signature = (val, *, ordering = Ordering::SeqCst))]
+            pub fn nand_and_fetch(
+                &self,
+                val: &Bound<'_, PyAny>,
+                ordering: Ordering,
+            ) -> PyResult<$int> {
+                let py = val.py();
+                let val = self.extract(val)?;
+                let prev = self.inner.fetch_nand(val, ordering.rmw());
+                self.watchers.wake(py, ordering.rmw());
+                Ok(!(prev & val))
+            }
+
+            #[pyo3(signature = (val, *, ordering = Ordering::SeqCst))]
+            pub fn max_and_fetch(
+                &self,
+                val: &Bound<'_, PyAny>,
+                ordering: Ordering,
+            ) -> PyResult<$int> {
+                let py = val.py();
+                let val = self.extract(val)?;
+                let prev = self.inner.fetch_max(val, ordering.rmw());
+                self.watchers.wake(py, ordering.rmw());
+                Ok(prev.max(val))
+            }
+
+            #[pyo3(signature = (val, *, ordering = Ordering::SeqCst))]
+            pub fn min_and_fetch(
+                &self,
+                val: &Bound<'_, PyAny>,
+                ordering: Ordering,
+            ) -> PyResult<$int> {
+                let py = val.py();
+                let val = self.extract(val)?;
+                let prev = self.inner.fetch_min(val, ordering.rmw());
+                self.watchers.wake(py, ordering.rmw());
+                Ok(prev.min(val))
+            }
+
+            #[pyo3(signature = (f, *, ordering = Ordering::SeqCst))]
+            pub fn fetch_update(&self, f: &Bound<'_, PyAny>, ordering: Ordering) -> PyResult<$int> {
+                Ok(self.fetch_update_impl(f, ordering)?.0)
+            }
+
+            #[pyo3(signature = (f, *, ordering = Ordering::SeqCst))]
+            pub fn update_and_get(
+                &self,
+                f: &Bound<'_, PyAny>,
+                ordering: Ordering,
+            ) -> PyResult<$int> {
+                Ok(self.fetch_update_impl(f, ordering)?.1)
+            }
+
+            #[pyo3(signature = (expected, timeout = None))]
+            pub fn wait(
+                &self,
+                py: Python<'_>,
+                expected: &Bound<'_, PyAny>,
+                timeout: Option<f64>,
+            ) -> PyResult<bool> {
+                let expected = self.extract(expected)?;
+                self.notifier
+                    .wait(py, timeout, || self.inner.load(SeqCst) == expected)
+            }
+
+            pub fn notify_one(&self) {
+                self.notifier.notify_one();
+            }
+
+            pub fn notify_all(&self) {
+                self.notifier.notify_all();
+            }
+
+            pub fn wait_for_change<'py>(
+                slf: &Bound<'py, Self>,
+                expected: &Bound<'py, PyAny>,
+            ) -> PyResult<Bound<'py, PyAny>> {
+                let expected = slf.get().extract(expected)?;
+                watch::wait(
+                    slf.as_any(),
+                    &slf.get().watchers,
+                    Condition::Changed {
+                        expected: expected.into_pyobject(slf.py())?.into_any().unbind(),
+                        identity: false,
+                    },
+                )
+            }
+
+            pub fn wait_until<'py>(
+                slf: &Bound<'py, Self>,
+                predicate: Py<PyAny>,
+            ) -> PyResult<Bound<'py, PyAny>> {
+                watch::wait(
+                    slf.as_any(),
+                    &slf.get().watchers,
+                    Condition::Until(predicate),
+                )
+            }
+
+            fn __repr__(&self) -> String {
+                // Only a mode other than the default is shown
+                let strict = if self.strict == $strict {
+                    ""
+                } else if self.strict {
+                    ", strict=True"
+                } else {
+                    ", strict=False"
+                };
+                format!(
+                    "{}({}{strict})",
+                    stringify!($name),
+                    self.inner.load(Relaxed)
+                )
+            }
+
+            fn __str__(&self) -> String {
+                self.inner.load(Relaxed).to_string()
+            }
+
+            fn __bool__(&self) -> bool {
+                self.inner.load(Relaxed) != 0
+            }
+
+            fn __reduce__<'py>(
+                slf: &Bound<'py, Self>,
+            ) -> PyResult<(Bound<'py, PyType>, ($int, bool))> {
+                let this = slf.get();
+                this.inner.check_detachable()?;
+                Ok((slf.get_type(), (this.inner.load(SeqCst), this.strict)))
+            }
+
+            fn __copy__(&self) -> PyResult<Self> {
+                self.inner.check_detachable()?;
+                Ok(Self::with_value(self.inner.load(SeqCst), self.strict))
+            }
 
-    pub fn fetch_xor(&self, val: i32) -> i32 {
-        self.inner.fetch_xor(val, Ordering::SeqCst)
+            fn __deepcopy__(&self, _memo: &Bound<'_, PyAny>) -> PyResult<Self> {
+                self.__copy__()
+            }
+
+            fn __int__(&self) -> $int {
+                self.inner.load(SeqCst)
+            }
+
+            fn __index__(&self) -> $int {
+                self.inner.load(SeqCst)
+            }
+
+            fn __float__(&self) -> f64 {
+                self.inner.load(SeqCst) as f64
+            }
+
+            fn __format__<'py>(
+                &self,
+                py: Python<'py>,
+                spec: &Bound<'py, PyAny>,
+            ) -> PyResult<Bound<'py, PyAny>> {
+                self.int(py)?.call_method1("__format__", (spec,))
+            }
+
+            // Compares the current value, so like other mutable objects with value
+            // equality the class is unhashable
+            fn __richcmp__<'py>(
+                &self,
+                other: &Bound<'py, PyAny>,
+                op: CompareOp,
+            ) -> PyResult<Bound<'py, PyAny>> {
+                self.int(other.py())?.rich_compare(other, op)
+            }
+
+            fn __add__<'py>(&self, other: &Bound<'py, PyAny>) -> PyResult<Bound<'py, PyAny>> {
+                self.int(other.py())?.call_method1("__add__", (other,))
+            }
+
+            fn __radd__<'py>(&self, other: &Bound<'py, PyAny>) -> PyResult<Bound<'py, PyAny>> {
+                self.int(other.py())?.call_method1("__radd__", (other,))
+            }
+
+            fn __sub__<'py>(&self, other: &Bound<'py, PyAny>) -> PyResult<Bound<'py, PyAny>> {
+                self.int(other.py())?.call_method1("__sub__", (other,))
+            }
+
+            fn __rsub__<'py>(&self, other: &Bound<'py, PyAny>) -> PyResult<Bound<'py, PyAny>> {
+                self.int(other.py())?.call_method1("__rsub__", (other,))
+            }
+
+            fn __mul__<'py>(&self, other: &Bound<'py, PyAny>) -> PyResult<Bound<'py, PyAny>> {
+                self.int(other.py())?.call_method1("__mul__", (other,))
+            }
+
+            fn __rmul__<'py>(&self, other: &Bound<'py, PyAny>) -> PyResult<Bound<'py, PyAny>> {
+                self.int(other.py())?.call_method1("__rmul__", (other,))
+            }
+
+            fn __truediv__<'py>(&self, other: &Bound<'py, PyAny>) -> PyResult<Bound<'py, PyAny>> {
+                self.int(other.py())?.call_method1("__truediv__", (other,))
+            }
+
+            fn __rtruediv__<'py>(&self, other: &Bound<'py, PyAny>) -> PyResult<Bound<'py, PyAny>> {
+                self.int(other.py())?.call_method1("__rtruediv__", (other,))
+            }
+
+            fn __floordiv__<'py>(&self, other: &Bound<'py, PyAny>) -> PyResult<Bound<'py, PyAny>> {
+                self.int(other.py())?.call_method1("__floordiv__", (other,))
+            }
+
+            fn __rfloordiv__<'py>(&self, other: &Bound<'py, PyAny>) -> PyResult<Bound<'py, PyAny>> {
+                self.int(other.py())?
+                    .call_method1("__rfloordiv__", (other,))
+            }
+
+            fn __mod__<'py>(&self, other: &Bound<'py, PyAny>) -> PyResult<Bound<'py, PyAny>> {
+                self.int(other.py())?.call_method1("__mod__", (other,))
+            }
+
+            fn __rmod__<'py>(&self, other: &Bound<'py, PyAny>) -> PyResult<Bound<'py, PyAny>> {
+                self.int(other.py())?.call_method1("__rmod__", (other,))
+            }
+
+            fn __pow__<'py>(
+                &self,
+                other: &Bound<'py, PyAny>,
+                modulo: &Bound<'py, PyAny>,
+            ) -> PyResult<Bound<'py, PyAny>> {
+                self.int(other.py())?
+                    .call_method1("__pow__", (other, modulo))
+            }
+
+            fn __rpow__<'py>(
+                &self,
+                other: &Bound<'py, PyAny>,
+                modulo: &Bound<'py, PyAny>,
+            ) -> PyResult<Bound<'py, PyAny>> {
+                self.int(other.py())?
+                    .call_method1("__rpow__", (other, modulo))
+            }
+
+            fn __lshift__<'py>(&self, other: &Bound<'py, PyAny>) -> PyResult<Bound<'py, PyAny>> {
+                self.int(other.py())?.call_method1("__lshift__", (other,))
+            }
+
+            fn __rlshift__<'py>(&self, other: &Bound<'py, PyAny>) -> PyResult<Bound<'py, PyAny>> {
+                self.int(other.py())?.call_method1("__rlshift__", (other,))
+            }
+
+            fn __rshift__<'py>(&self, other: &Bound<'py, PyAny>) -> PyResult<Bound<'py, PyAny>> {
+                self.int(other.py())?.call_method1("__rshift__", (other,))
+            }
+
+            fn __rrshift__<'py>(&self, other: &Bound<'py, PyAny>) -> PyResult<Bound<'py, PyAny>> {
+                self.int(other.py())?.call_method1("__rrshift__", (other,))
+            }
+
+            fn __and__<'py>(&self, other: &Bound<'py, PyAny>) -> PyResult<Bound<'py, PyAny>> {
+                self.int(other.py())?.call_method1("__and__", (other,))
+            }
+
+            fn __rand__<'py>(&self, other: &Bound<'py, PyAny>) -> PyResult<Bound<'py, PyAny>> {
+                self.int(other.py())?.call_method1("__rand__", (other,))
+            }
+
+            fn __or__<'py>(&self, other: &Bound<'py, PyAny>) -> PyResult<Bound<'py, PyAny>> {
+                self.int(other.py())?.call_method1("__or__", (other,))
+            }
+
+            fn __ror__<'py>(&self, other: &Bound<'py, PyAny>) -> PyResult<Bound<'py, PyAny>> {
+                self.int(other.py())?.call_method1("__ror__", (other,))
+            }
+
+            fn __xor__<'py>(&self, other: &Bound<'py, PyAny>) -> PyResult<Bound<'py, PyAny>> {
+                self.int(other.py())?.call_method1("__xor__", (other,))
+            }
+
+            fn __rxor__<'py>(&self, other: &Bound<'py, PyAny>) -> PyResult<Bound<'py, PyAny>> {
+                self.int(other.py())?.call_method1("__rxor__", (other,))
+            }
+
+            fn __neg__<'py>(&self, py: Python<'py>) -> PyResult<Bound<'py, PyAny>> {
+                self.int(py)?.call_method0("__neg__")
+            }
+
+            fn __pos__<'py>(&self, py: Python<'py>) -> PyResult<Bound<'py, PyAny>> {
+                self.int(py)?.call_method0("__pos__")
+            }
+
+            fn __abs__<'py>(&self, py: Python<'py>) -> PyResult<Bound<'py, PyAny>> {
+                self.int(py)?.call_method0("__abs__")
+            }
+
+            fn __invert__<'py>(&self, py: Python<'py>) -> PyResult<Bound<'py, PyAny>> {
+                self.int(py)?.call_method0("__invert__")
+            }
+
+            fn __iadd__(&self, other: &Bound<'_, PyAny>) -> PyResult<()> {
+                self.fetch_add(other, Ordering::SeqCst)?;
+                Ok(())
+            }
+
+            fn __isub__(&self, other: &Bound<'_, PyAny>) -> PyResult<()> {
+                self.fetch_sub(other, Ordering::SeqCst)?;
+                Ok(())
+            }
+
+            fn __iand__(&self, other: &Bound<'_, PyAny>) -> PyResult<()> {
+                self.fetch_and(other, Ordering::SeqCst)?;
+                Ok(())
+            }
+
+            fn __ior__(&self, other: &Bound<'_, PyAny>) -> PyResult<()> {
+                self.fetch_or(other, Ordering::SeqCst)?;
+                Ok(())
+            }
+
+            fn __ixor__(&self, other: &Bound<'_, PyAny>) -> PyResult<()> {
+                self.fetch_xor(other, Ordering::SeqCst)?;
+                Ok(())
+            }
+
+            fn __traverse__(&self, visit: PyVisit<'_>) -> Result<(), PyTraverseError> {
+                self.watchers.traverse(&visit)
+            }
+
+            fn __clear__(&self) {
+                self.watchers.clear();
+            }
+        }
+
+        impl $name {
+            fn with_value(val: $int, strict: bool) -> Self {
+                Self::with_storage(Storage::Inline(<$atomic>::new(val)), strict)
+            }
+
+            fn with_storage(inner: Storage<$atomic>, strict: bool) -> Self {
+                Self {
+                    inner,
+                    strict,
+                    notifier: Notifier::new(),
+                    watchers: Watchers::new(),
+                }
+            }
+
+            fn fetch_update_impl(
+                &self,
+                f: &Bound<'_, PyAny>,
+                ordering: Ordering,
+            ) -> PyResult<($int, $int)> {
+                let (set, fetch) = Ordering::compare_exchange(ordering, None)?;
+                let mut prev = self.inner.load(fetch);
+                loop {
+                    let next = f.call1((prev,))?;
+                    if is_abort(&next)? {
+                        return Ok((prev, prev));
+                    }
+                    let next = self.extract(&next)?;
+                    match self.inner.compare_exchange_weak(prev, next, set, fetch) {
+                        Ok(_) => {
+                            self.watchers.wake(f.py(), set);
+                            return Ok((prev, next));
+                        }
+                        Err(cur) => prev = cur,
+                    }
+                }
+            }
+
+            // A snapshot of the value as a python int
+            fn int<'py>(&self, py: Python<'py>) -> PyResult<Bound<'py, PyAny>> {
+                Ok(self.inner.load(SeqCst).into_pyobject(py)?.into_any())
+            }
+
+            fn extract(&self, val: &Bound<'_, PyAny>) -> PyResult<$int> {
+                if self.strict {
+                    val.extract()
+                } else {
+                    Ok(wrapping_bits(val)? as $int)
+                }
+            }
+        }
+    };
+}
+
+// The low 64 bits of any python int, so that truncating them wraps around in
+// two's complement like `haxe.Int32` does
+fn wrapping_bits(val: &Bound<'_, PyAny>) -> PyResult<u64> {
+    // Before 3.10 masking falls back to `__int__`, which would truncate floats
+    // Safety: `val` is a valid python object
+    let index =
+        unsafe { Bound::from_owned_ptr_or_err(val.py(), pyo3::ffi::PyNumber_Index(val.as_ptr()))? };
+    // Safety: `index` is a valid python int
+    let bits = unsafe { pyo3::ffi::PyLong_AsUnsignedLongLongMask(index.as_ptr()) };
+    if bits == u64::MAX {
+        if let Some(err) = PyErr::take(val.py()) {
+            return Err(err);
+        }
     }
+    Ok(bits)
 }
 
+// Only `AtomicInt` stands in for `haxe.Int32`, so only it wraps by default
+atomic_int!(AtomicInt, atomic::AtomicI32, i32, strict = false);
+atomic_int!(AtomicInt64, AtomicI64, i64, strict = true);
+atomic_int!(AtomicUInt32, atomic::AtomicU32, u32, strict = true);
+atomic_int!(AtomicUInt64, AtomicU64, u64, strict = true);
+
 #[pyclass(module = "haxe_atomic", frozen)]
 #[derive(Debug)]
 pub struct AtomicObject {
-    // Invariant: contains an owned pointer to a valid python object
+    // Invariant: contains an owned pointer to a valid python object, or null once cleared
     value: AtomicPtr<pyo3::ffi::PyObject>,
+    // Owned pointers removed from `value` must be released through `domain`
+    domain: Domain,
+    // Compare objects by identity instead of `__eq__` in `compare_exchange`
+    identity: bool,
+    watchers: Arc<Watchers>,
 }
 
 #[pymethods]
 impl AtomicObject {
     #[new]
-    fn new(val: Py<PyAny>) -> Self {
+    #[pyo3(signature = (val, identity = false))]
+    fn new(val: Py<PyAny>, identity: bool) -> Self {
         Self {
             value: AtomicPtr::new(val.into_ptr()),
+            domain: Domain::new(),
+            identity,
+            watchers: Watchers::new(),
         }
     }
 
-    pub fn load(&self, token: Python) -> Py<PyAny> {
-        // Safety: `self.value` contains a pointer to a python object
-        unsafe { Py::from_borrowed_ptr(token, self.value.load(Ordering::SeqCst)) }
+    // The slot itself is always accessed with `SeqCst`, see `Slot`. Requested
+    // orderings are validated like for the other atomics but can only ever be
+    // strengthened.
+
+    #[pyo3(signature = (*, ordering = Ordering::SeqCst))]
+    pub fn load(&self, token: Python, ordering: Ordering) -> PyResult<Py<PyAny>> {
+        ordering.load()?;
+        Ok(self.load_bound(token).unbind())
     }
 
-    pub fn store(&self, val: Bound<PyAny>) -> Py<PyAny> {
+    #[pyo3(signature = (val, *, ordering = Ordering::SeqCst))]
+    pub fn store(&self, val: Bound<PyAny>, ordering: Ordering) -> PyResult<Py<PyAny>> {
+        ordering.store()?;
         let ret = val.clone();
-        let old = self.value.swap(val.into_ptr(), Ordering::SeqCst);
-        // Safety: the GIL is held and `old` is a valid pointer
-        unsafe { pyo3::ffi::Py_DecRef(old) };
-        ret.unbind()
-    }
-
-    pub fn exchange(&self, val: Bound<PyAny>) -> Py<PyAny> {
-        let token = val.py();
-        let old_value = self.value.swap(val.into_ptr(), Ordering::SeqCst);
-        // Safety: `self.value` always contains a pointer to a python object
-        // so `old_value` is valid pointer
-        // `old_value` is not stored in `self.value` anymore and is thus owned
-        unsafe { Py::from_owned_ptr(token, old_value) }
-    }
-
-    pub fn compare_exchange<'a>(
-        &'a self,
-        expected: Bound<'a, PyAny>,
-        mut desired: Bound<'a, PyAny>,
-    ) -> PyResult<Py<PyAny>> {
-        let py = expected.py();
-        let mut orig: Bound<PyAny> = self.load(py).into_bound(py);
-        while orig.eq(&expected)? {
-            let desired_ptr = desired.into_ptr();
-            match self.value.compare_exchange(
-                orig.as_ptr(),
-                desired_ptr,
-                Ordering::SeqCst,
-                Ordering::SeqCst,
-            ) {
-                Ok(orig) => return Ok(unsafe { Py::from_owned_ptr(py, orig) }),
-                Err(cur_val) => {
-                    // Safety: `cur_val` is a pointer to a valid python object (see invariant of self.value)
-                    orig = unsafe { Bound::from_borrowed_ptr(py, cur_val) };
-                    // Safety: `desired` has not been stored in self.value and is thus still owned
-                    desired = unsafe { Bound::from_owned_ptr(py, cur_val) };
-                }
-            }
+        self.slot().swap(val);
+        Ok(ret.unbind())
+    }
+
+    #[pyo3(signature = (val, *, ordering = Ordering::SeqCst))]
+    #[allow(unused_variables)]
+    pub fn exchange(&self, val: Bound<PyAny>, ordering: Ordering) -> Py<PyAny> {
+        self.slot().swap(val).unbind()
+    }
+
+    #[pyo3(signature = (expected, desired, *, success = Ordering::SeqCst, failure = None))]
+    pub fn compare_exchange<'py>(
+        &self,
+        expected: &Bound<'py, PyAny>,
+        desired: &Bound<'py, PyAny>,
+        success: Ordering,
+        failure: Option<Ordering>,
+    ) -> PyResult<Bound<'py, PyAny>> {
+        Ok(self
+            .compare_exchange_result(expected, desired, success, failure)?
+            .1)
+    }
+
+    #[pyo3(signature = (expected, desired, *, success = Ordering::SeqCst, failure = None))]
+    pub fn compare_exchange_result<'py>(
+        &self,
+        expected: &Bound<'py, PyAny>,
+        desired: &Bound<'py, PyAny>,
+        success: Ordering,
+        failure: Option<Ordering>,
+    ) -> PyResult<(bool, Bound<'py, PyAny>)> {
+        Ordering::compare_exchange(success, failure)?;
+        self.slot()
+            .compare_exchange(expected, desired, self.identity, false)
+    }
+
+    #[pyo3(signature = (expected, desired, *, success = Ordering::SeqCst, failure = None))]
+    pub fn compare_exchange_weak<'py>(
+        &self,
+        expected: &Bound<'py, PyAny>,
+        desired: &Bound<'py, PyAny>,
+        success: Ordering,
+        failure: Option<Ordering>,
+    ) -> PyResult<(bool, Bound<'py, PyAny>)> {
+        Ordering::compare_exchange(success, failure)?;
+        self.slot()
+            .compare_exchange(expected, desired, self.identity, true)
+    }
+
+    #[pyo3(signature = (expected, desired, *, success = Ordering::SeqCst, failure = None))]
+    pub fn compare_exchange_identity<'py>(
+        &self,
+        expected: &Bound<'py, PyAny>,
+        desired: &Bound<'py, PyAny>,
+        success: Ordering,
+        failure: Option<Ordering>,
+    ) -> PyResult<Bound<'py, PyAny>> {
+        Ordering::compare_exchange(success, failure)?;
+        Ok(self
+            .slot()
+            .compare_exchange(expected, desired, true, false)?
+            .1)
+    }
+
+    #[pyo3(signature = (f, *, ordering = Ordering::SeqCst))]
+    #[allow(unused_variables)]
+    pub fn fetch_update<'py>(
+        &self,
+        f: &Bound<'py, PyAny>,
+        ordering: Ordering,
+    ) -> PyResult<Bound<'py, PyAny>> {
+        Ok(self.slot().fetch_update(f)?.0)
+    }
+
+    #[pyo3(signature = (f, *, ordering = Ordering::SeqCst))]
+    #[allow(unused_variables)]
+    pub fn update_and_get<'py>(
+        &self,
+        f: &Bound<'py, PyAny>,
+        ordering: Ordering,
+    ) -> PyResult<Bound<'py, PyAny>> {
+        Ok(self.slot().fetch_update(f)?.1)
+    }
+
+    fn __repr__(slf: &Bound<'_, Self>) -> PyResult<String> {
+        let identity = if slf.get().identity {
+            ", identity=True"
+        } else {
+            ""
+        };
+        let value = slf
+            .get()
+            .guarded(slf, |value| Ok(value.repr()?.to_string()))?;
+        Ok(format!(
+            "AtomicObject({}{identity})",
+            value.as_deref().unwrap_or("...")
+        ))
+    }
+
+    fn __str__(slf: &Bound<'_, Self>) -> PyResult<String> {
+        match slf
+            .get()
+            .guarded(slf, |value| Ok(value.str()?.to_string()))?
+        {
+            Some(value) => Ok(value),
+            None => Self::__repr__(slf),
         }
-        Ok(orig.unbind())
     }
 
-    fn __traverse__(&self, visit: PyVisit<'_>) -> Result<(), PyTraverseError> {
-        // The following must use a method that does not increment the ref count
-        // otherwise the cycle collector may fail to detect cycles
-        let object = std::mem::ManuallyDrop::new(unsafe {
-            Py::<PyAny>::from_owned_ptr_or_opt(
-                Python::assume_gil_acquired(),
-                self.value.load(Ordering::SeqCst),
-            )
-        });
-        visit.call(&*object)?;
+    // The value is restored through `__setstate__` so that an object containing
+    // itself can be pickled
+    #[allow(clippy::type_complexity)]
+    fn __reduce__<'py>(
+        slf: &Bound<'py, Self>,
+    ) -> (Bound<'py, PyType>, (Py<PyAny>, bool), Bound<'py, PyAny>) {
+        let py = slf.py();
+        let this = slf.get();
+        (
+            slf.get_type(),
+            (py.None(), this.identity),
+            this.load_bound(py),
+        )
+    }
+
+    fn __setstate__(&self, state: Bound<'_, PyAny>) -> PyResult<()> {
+        self.store(state, Ordering::SeqCst)?;
         Ok(())
     }
 
+    fn __copy__(&self, py: Python<'_>) -> Self {
+        Self::new(self.load_bound(py).unbind(), self.identity)
+    }
+
+    fn __deepcopy__<'py>(
+        slf: &Bound<'py, Self>,
+        memo: &Bound<'py, PyAny>,
+    ) -> PyResult<Bound<'py, Self>> {
+        let py = slf.py();
+        let this = slf.get();
+        let copy = Bound::new(py, Self::new(py.None(), this.identity))?;
+        // Register the copy before copying the referent, which may refer back to `slf`
+        memo.set_item(slf.as_ptr() as usize, &copy)?;
+        let value = py
+            .import("copy")?
+            .getattr("deepcopy")?
+            .call1((this.load_bound(py), memo))?;
+        copy.get().store(value, Ordering::SeqCst)?;
+        Ok(copy)
+    }
+
+    pub fn wait_for_change<'py>(
+        slf: &Bound<'py, Self>,
+        expected: Py<PyAny>,
+    ) -> PyResult<Bound<'py, PyAny>> {
+        let this = slf.get();
+        watch::wait(
+            slf.as_any(),
+            &this.watchers,
+            Condition::Changed {
+                expected,
+                identity: this.identity,
+            },
+        )
+    }
+
+    pub fn wait_until<'py>(
+        slf: &Bound<'py, Self>,
+        predicate: Py<PyAny>,
+    ) -> PyResult<Bound<'py, PyAny>> {
+        watch::wait(
+            slf.as_any(),
+            &slf.get().watchers,
+            Condition::Until(predicate),
+        )
+    }
+
+    fn __traverse__(&self, visit: PyVisit<'_>) -> Result<(), PyTraverseError> {
+        self.slot().traverse(&visit)?;
+        self.watchers.traverse(&visit)
+    }
+
     fn __clear__(&self) {
-        // Clear reference and decrement its ref counter
-        let ptr = self.value.swap(core::ptr::null_mut(), Ordering::SeqCst);
-        // Safety: the GIL is held and `ptr` is either valid or null
-        unsafe {
-            pyo3::ffi::Py_DecRef(ptr);
+        self.slot().clear();
+        self.watchers.clear();
+    }
+}
+
+impl AtomicObject {
+    fn slot(&self) -> Slot<'_> {
+        // Safety: `self.value` upholds the slot invariant and is only released through `self.domain`
+        unsafe { Slot::new(&self.value, &self.domain, Some(&self.watchers)) }
+    }
+
+    // Apply `f` to a snapshot of the stored object, unless this is a recursive call
+    // for the same atomic because it (indirectly) contains itself
+    fn guarded<T>(
+        &self,
+        slf: &Bound<'_, Self>,
+        f: impl FnOnce(&Bound<'_, PyAny>) -> PyResult<T>,
+    ) -> PyResult<Option<T>> {
+        let py = slf.py();
+        // Safety: `slf` is a valid python object
+        match unsafe { pyo3::ffi::Py_ReprEnter(slf.as_ptr()) } {
+            0 => {}
+            rc if rc > 0 => return Ok(None),
+            _ => return Err(PyErr::fetch(py)),
         }
+        let res = f(&self.load_bound(py));
+        // Safety: `Py_ReprEnter` succeeded for `slf` above
+        unsafe { pyo3::ffi::Py_ReprLeave(slf.as_ptr()) };
+        res.map(Some)
+    }
+
+    fn load_bound<'py>(&self, py: Python<'py>) -> Bound<'py, PyAny> {
+        self.slot().load(py)
     }
 }
 
 impl Drop for AtomicObject {
     fn drop(&mut self) {
-        self.__clear__();
+        // Safety: nothing else can access `self.value` anymore, and the GIL is held
+        unsafe { pyo3::ffi::Py_DecRef(*self.value.get_mut()) };
     }
 }
@@ -0,0 +1,275 @@
+//! A hash map split into stripes, each a python `dict` behind its own lock.
+//!
+//! Operations that modify a stripe lock it, so that compound operations like
+//! [`ConcurrentMap::compute_if_absent`] are atomic, while lookups rely on the
+//! `dict` being safe to read concurrently. Threads waiting for a stripe release
+//! the GIL, since the thread holding it may run python code to hash and compare
+//! keys. A thread holding a stripe cannot modify any other stripe of the same
+//! map, so that two threads can never wait for each other's stripes of one map.
+//! Modifying another map is allowed, which can deadlock with a thread doing the
+//! reverse just like nested locks taken in opposite orders.
+
+use crate::{padded::CachePadded, sync};
+use pyo3::{
+    exceptions::{PyRuntimeError, PyValueError},
+    prelude::*,
+    types::{PyDict, PyList, PyTuple},
+    PyTraverseError, PyVisit,
+};
+use std::{
+    cell::RefCell,
+    sync::{Condvar, Mutex as StdMutex},
+};
+
+thread_local! {
+    // Maps this thread holds a stripe of, innermost last
+    static HOLDING: RefCell<Vec<*const ConcurrentMap>> = const { RefCell::new(Vec::new()) };
+}
+
+#[derive(Debug)]
+struct Stripe {
+    // Whether a thread is modifying `dict`
+    locked: StdMutex<bool>,
+    released: Condvar,
+    dict: Py<PyDict>,
+}
+
+struct Locked<'a, 'py> {
+    map: &'a ConcurrentMap,
+    stripe: &'a Stripe,
+    dict: Bound<'py, PyDict>,
+}
+
+impl Drop for Locked<'_, '_> {
+    fn drop(&mut self) {
+        *sync::lock(&self.stripe.locked) = false;
+        self.stripe.released.notify_one();
+        HOLDING.with_borrow_mut(|holding| {
+            if let Some(pos) = holding.iter().rposition(|&map| std::ptr::eq(map, self.map)) {
+                holding.remove(pos);
+            }
+        });
+    }
+}
+
+/// A hash map for any number of threads with atomic compound operations.
+#[pyclass(module = "haxe_atomic", frozen)]
+#[derive(Debug)]
+pub struct ConcurrentMap {
+    stripes: Box<[CachePadded<Stripe>]>,
+}
+
+#[pymethods]
+impl ConcurrentMap {
+    #[new]
+    #[pyo3(signature = (stripes = 16))]
+    fn new(py: Python<'_>, stripes: usize) -> PyResult<Self> {
+        if stripes == 0 {
+            return Err(PyValueError::new_err("stripes must be positive"));
+        }
+        Ok(Self {
+            stripes: (0..stripes)
+                .map(|_| {
+                    CachePadded(Stripe {
+                        locked: StdMutex::new(false),
+                        released: Condvar::new(),
+                        dict: PyDict::new(py).unbind(),
+                    })
+                })
+                .collect(),
+        })
+    }
+
+    #[pyo3(signature = (key, default = None))]
+    pub fn get(&self, key: &Bound<'_, PyAny>, default: Option<Py<PyAny>>) -> PyResult<Py<PyAny>> {
+        let py = key.py();
+        Ok(match self.stripe(key)?.dict.bind(py).get_item(key)? {
+            Some(value) => value.unbind(),
+            None => default.unwrap_or_else(|| py.None()),
+        })
+    }
+
+    /// Map `key` to `value`, returning the value it replaced, if any.
+    pub fn insert(
+        &self,
+        key: &Bound<'_, PyAny>,
+        value: &Bound<'_, PyAny>,
+    ) -> PyResult<Option<Py<PyAny>>> {
+        let locked = self.lock(key)?;
+        let previous = locked.dict.get_item(key)?;
+        locked.dict.set_item(key, value)?;
+        Ok(previous.map(Bound::unbind))
+    }
+
+    /// Remove `key`, returning its value or `default` if it was not present.
+    #[pyo3(signature = (key, default = None))]
+    pub fn remove(
+        &self,
+        key: &Bound<'_, PyAny>,
+        default: Option<Py<PyAny>>,
+    ) -> PyResult<Py<PyAny>> {
+        let py = key.py();
+        let locked = self.lock(key)?;
+        Ok(match locked.dict.get_item(key)? {
+            Some(value) => {
+                locked.dict.del_item(key)?;
+                value.unbind()
+            }
+            None => default.unwrap_or_else(|| py.None()),
+        })
+    }
+
+    /// Return the value of `key`, first mapping it to `func(key)` if it is not
+    /// present. `func` is called at most once per absent key, and other threads
+    /// modifying keys of the same stripe wait for it. `func` may read the map but
+    /// not modify it.
+    pub fn compute_if_absent(
+        &self,
+        key: &Bound<'_, PyAny>,
+        func: &Bound<'_, PyAny>,
+    ) -> PyResult<Py<PyAny>> {
+        let locked = self.lock(key)?;
+        if let Some(value) = locked.dict.get_item(key)? {
+            return Ok(value.unbind());
+        }
+        let value = func.call1((key,))?;
+        locked.dict.set_item(key, &value)?;
+        Ok(value.unbind())
+    }
+
+    /// Map `key` to `new` if it is currently mapped to a value equal to
+    /// `expected`, returning whether it was.
+    pub fn compare_and_set(
+        &self,
+        key: &Bound<'_, PyAny>,
+        expected: &Bound<'_, PyAny>,
+        new: &Bound<'_, PyAny>,
+    ) -> PyResult<bool> {
+        let locked = self.lock(key)?;
+        match locked.dict.get_item(key)? {
+            Some(current) if current.is(expected) || current.eq(expected)? => {
+                locked.dict.set_item(key, new)?;
+                Ok(true)
+            }
+            _ => Ok(false),
+        }
+    }
+
+    /// Number of entries, which may be outdated by concurrent operations.
+    fn __len__(&self, py: Python<'_>) -> usize {
+        self.stripes
+            .iter()
+            .map(|stripe| stripe.dict.bind(py).len())
+            .sum()
+    }
+
+    fn __contains__(&self, key: &Bound<'_, PyAny>) -> PyResult<bool> {
+        self.stripe(key)?.dict.bind(key.py()).contains(key)
+    }
+
+    // Keys of one stripe after another, each copied when the iterator reaches it
+    fn __iter__(slf: Bound<'_, Self>) -> MapIter {
+        MapIter::new(slf, false)
+    }
+
+    /// Iterate over `(key, value)` pairs like `__iter__` does over keys.
+    fn items(slf: Bound<'_, Self>) -> MapIter {
+        MapIter::new(slf, true)
+    }
+
+    fn __traverse__(&self, visit: PyVisit<'_>) -> Result<(), PyTraverseError> {
+        for stripe in self.stripes.iter() {
+            visit.call(&stripe.dict)?;
+        }
+        Ok(())
+    }
+
+    fn __clear__(&self, py: Python<'_>) {
+        for stripe in self.stripes.iter() {
+            stripe.dict.bind(py).clear();
+        }
+    }
+}
+
+impl ConcurrentMap {
+    fn stripe(&self, key: &Bound<'_, PyAny>) -> PyResult<&Stripe> {
+        let hash = key.hash()? as usize;
+        Ok(&self.stripes[hash % self.stripes.len()])
+    }
+
+    // Lock the stripe of `key` for modifying it
+    fn lock<'a, 'py>(&'a self, key: &Bound<'py, PyAny>) -> PyResult<Locked<'a, 'py>> {
+        let me = self as *const Self;
+        // The function passed to `compute_if_absent` could otherwise wait for a
+        // stripe held by another thread that is waiting for ours
+        if HOLDING.with_borrow(|holding| holding.contains(&me)) {
+            return Err(PyRuntimeError::new_err(
+                "cannot modify a ConcurrentMap during another operation on it",
+            ));
+        }
+        let stripe = self.stripe(key)?;
+        let py = key.py();
+        sync::block(py, &stripe.locked, &stripe.released, None, |locked| {
+            !std::mem::replace(locked, true)
+        })?;
+        HOLDING.with_borrow_mut(|holding| holding.push(me));
+        Ok(Locked {
+            map: self,
+            stripe,
+            dict: stripe.dict.bind(py).clone(),
+        })
+    }
+}
+
+#[pyclass(module = "haxe_atomic")]
+pub struct MapIter {
+    map: Py<ConcurrentMap>,
+    items: bool,
+    // Index of the next stripe to copy
+    stripe: usize,
+    // Entries of the last stripe copied, as `(key, value)` tuples
+    pending: Py<PyList>,
+    pos: usize,
+}
+
+impl MapIter {
+    fn new(map: Bound<'_, ConcurrentMap>, items: bool) -> Self {
+        Self {
+            pending: PyList::empty(map.py()).unbind(),
+            map: map.unbind(),
+            items,
+            stripe: 0,
+            pos: 0,
+        }
+    }
+}
+
+#[pymethods]
+impl MapIter {
+    fn __iter__(slf: PyRef<'_, Self>) -> PyRef<'_, Self> {
+        slf
+    }
+
+    fn __next__(&mut self, py: Python<'_>) -> PyResult<Option<Py<PyAny>>> {
+        let map = self.map.get();
+        while self.pos == self.pending.bind(py).len() {
+            let Some(stripe) = map.stripes.get(self.stripe) else {
+                return Ok(None);
+            };
+            self.pending = stripe.dict.bind(py).items().unbind();
+            self.stripe += 1;
+            self.pos = 0;
+        }
+        let entry = self.pending.bind(py).get_item(self.pos)?;
+        self.pos += 1;
+        if self.items {
+            return Ok(Some(entry.unbind()));
+        }
+        Ok(Some(entry.downcast::<PyTuple>()?.get_item(0)?.unbind()))
+    }
+
+    fn __traverse__(&self, visit: PyVisit<'_>) -> Result<(), PyTraverseError> {
+        visit.call(&self.map)?;
+        visit.call(&self.pending)
+    }
+}
@@ -0,0 +1,70 @@
+use pyo3::{exceptions::PyValueError, prelude::*};
+use std::sync::atomic;
+
+#[pyclass(module = "haxe_atomic", frozen, eq, eq_int)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Ordering {
+    Relaxed,
+    Acquire,
+    Release,
+    AcqRel,
+    SeqCst,
+}
+
+impl Ordering {
+    pub fn load(self) -> PyResult<atomic::Ordering> {
+        match self {
+            Ordering::Release | Ordering::AcqRel => Err(PyValueError::new_err(format!(
+                "{self:?} is not a valid ordering for a load"
+            ))),
+            _ => Ok(self.into()),
+        }
+    }
+
+    pub fn store(self) -> PyResult<atomic::Ordering> {
+        match self {
+            Ordering::Acquire | Ordering::AcqRel => Err(PyValueError::new_err(format!(
+                "{self:?} is not a valid ordering for a store"
+            ))),
+            _ => Ok(self.into()),
+        }
+    }
+
+    pub fn rmw(self) -> atomic::Ordering {
+        self.into()
+    }
+
+    /// Validate a `success`/`failure` pair, deriving the failure ordering from
+    /// the success ordering when it is not given.
+    pub fn compare_exchange(
+        success: Ordering,
+        failure: Option<Ordering>,
+    ) -> PyResult<(atomic::Ordering, atomic::Ordering)> {
+        let failure = match failure {
+            Some(order @ (Ordering::Release | Ordering::AcqRel)) => {
+                return Err(PyValueError::new_err(format!(
+                    "{order:?} is not a valid failure ordering for a compare-exchange"
+                )))
+            }
+            Some(failure) => failure,
+            None => match success {
+                Ordering::Release => Ordering::Relaxed,
+                Ordering::AcqRel => Ordering::Acquire,
+                _ => success,
+            },
+        };
+        Ok((success.into(), failure.into()))
+    }
+}
+
+impl From<Ordering> for atomic::Ordering {
+    fn from(value: Ordering) -> Self {
+        match value {
+            Ordering::Relaxed => atomic::Ordering::Relaxed,
+            Ordering::Acquire => atomic::Ordering::Acquire,
+            Ordering::Release => atomic::Ordering::Release,
+            Ordering::AcqRel => atomic::Ordering::AcqRel,
+            Ordering::SeqCst => atomic::Ordering::SeqCst,
+        }
+    }
+}
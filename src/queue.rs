@@ -0,0 +1,428 @@
+//! Multi-producer multi-consumer queues of python objects.
+//!
+//! Bounded queues are a ring of sequenced cells after Dmitry Vyukov's design,
+//! unbounded ones a Michael-Scott linked list whose unlinked nodes are freed
+//! through a [`Domain`]. Queued objects are owned references that move from the
+//! pushing to the popping thread.
+
+use crate::{reclaim::Domain, wait::Notifier};
+use pyo3::{
+    create_exception,
+    exceptions::{PyException, PyTimeoutError, PyValueError},
+    ffi,
+    prelude::*,
+    PyTraverseError, PyVisit,
+};
+use std::{
+    mem::ManuallyDrop,
+    ptr,
+    sync::atomic::{AtomicBool, AtomicIsize, AtomicPtr, AtomicUsize, Ordering::SeqCst},
+};
+
+create_exception!(
+    haxe_atomic,
+    QueueClosed,
+    PyException,
+    "Raised when pushing to a closed queue, or popping from one that is closed and empty."
+);
+
+#[derive(Debug)]
+struct Cell {
+    // Twice the position that may push into the cell next, or one more than
+    // twice the position that may pop from it. Doubling keeps the two apart when
+    // the ring has a single cell
+    seq: AtomicUsize,
+    value: AtomicPtr<ffi::PyObject>,
+}
+
+#[derive(Debug)]
+struct Ring {
+    cells: Box<[Cell]>,
+    head: AtomicUsize,
+    tail: AtomicUsize,
+}
+
+impl Ring {
+    fn new(capacity: usize) -> Self {
+        Self {
+            cells: (0..capacity)
+                .map(|i| Cell {
+                    seq: AtomicUsize::new(2 * i),
+                    value: AtomicPtr::new(ptr::null_mut()),
+                })
+                .collect(),
+            head: AtomicUsize::new(0),
+            tail: AtomicUsize::new(0),
+        }
+    }
+
+    // Move the owned reference `val` into the ring, handing it back if the ring is full
+    fn push(&self, val: *mut ffi::PyObject) -> Result<(), *mut ffi::PyObject> {
+        let mut pos = self.tail.load(SeqCst);
+        loop {
+            let cell = &self.cells[pos % self.cells.len()];
+            let seq = cell.seq.load(SeqCst);
+            match seq.wrapping_sub(pos.wrapping_mul(2)) as isize {
+                0 => match self
+                    .tail
+                    .compare_exchange_weak(pos, pos.wrapping_add(1), SeqCst, SeqCst)
+                {
+                    Ok(_) => {
+                        cell.value.store(val, SeqCst);
+                        cell.seq.store(pos.wrapping_mul(2).wrapping_add(1), SeqCst);
+                        return Ok(());
+                    }
+                    Err(current) => pos = current,
+                },
+                // The cell still holds the value from the previous lap
+                diff if diff < 0 => return Err(val),
+                _ => pos = self.tail.load(SeqCst),
+            }
+        }
+    }
+
+    fn pop(&self) -> Option<*mut ffi::PyObject> {
+        let mut pos = self.head.load(SeqCst);
+        loop {
+            let cell = &self.cells[pos % self.cells.len()];
+            let seq = cell.seq.load(SeqCst);
+            match seq.wrapping_sub(pos.wrapping_mul(2).wrapping_add(1)) as isize {
+                0 => match self
+                    .head
+                    .compare_exchange_weak(pos, pos.wrapping_add(1), SeqCst, SeqCst)
+                {
+                    Ok(_) => {
+                        let val = cell.value.swap(ptr::null_mut(), SeqCst);
+                        let next = pos.wrapping_add(self.cells.len());
+                        cell.seq.store(next.wrapping_mul(2), SeqCst);
+                        return Some(val);
+                    }
+                    Err(current) => pos = current,
+                },
+                // Nothing was pushed into the cell in this lap yet
+                diff if diff < 0 => return None,
+                _ => pos = self.head.load(SeqCst),
+            }
+        }
+    }
+
+    fn for_each(&self, mut f: impl FnMut(*mut ffi::PyObject)) {
+        for cell in self.cells.iter() {
+            let val = cell.value.load(SeqCst);
+            if !val.is_null() {
+                f(val);
+            }
+        }
+    }
+}
+
+#[derive(Debug)]
+struct Node {
+    // Owned reference, null for the sentinel at the head of the list
+    value: AtomicPtr<ffi::PyObject>,
+    next: AtomicPtr<Node>,
+}
+
+impl Node {
+    fn new(value: *mut ffi::PyObject) -> *mut Self {
+        Box::into_raw(Box::new(Self {
+            value: AtomicPtr::new(value),
+            next: AtomicPtr::new(ptr::null_mut()),
+        }))
+    }
+}
+
+#[derive(Debug)]
+struct List {
+    // Invariant: both point to nodes of the list, which starts with a sentinel.
+    // Nodes unlinked from the head must be freed through `domain`
+    head: AtomicPtr<Node>,
+    tail: AtomicPtr<Node>,
+    domain: Domain,
+}
+
+impl List {
+    fn new() -> Self {
+        let sentinel = Node::new(ptr::null_mut());
+        Self {
+            head: AtomicPtr::new(sentinel),
+            tail: AtomicPtr::new(sentinel),
+            domain: Domain::new(),
+        }
+    }
+
+    fn push(&self, py: Python<'_>, val: *mut ffi::PyObject) {
+        let node = Node::new(val);
+        let _guard = self.domain.pin(py);
+        loop {
+            let tail = self.tail.load(SeqCst);
+            // Safety: nodes reachable from the list are not freed while pinned
+            let next = unsafe { &(*tail).next };
+            match next.compare_exchange(ptr::null_mut(), node, SeqCst, SeqCst) {
+                Ok(_) => {
+                    let _ = self.tail.compare_exchange(tail, node, SeqCst, SeqCst);
+                    return;
+                }
+                // Help the pushing thread that linked `next` but did not advance `tail` yet
+                Err(next) => {
+                    let _ = self.tail.compare_exchange(tail, next, SeqCst, SeqCst);
+                }
+            }
+        }
+    }
+
+    fn pop(&self, py: Python<'_>) -> Option<*mut ffi::PyObject> {
+        let _guard = self.domain.pin(py);
+        loop {
+            let head = self.head.load(SeqCst);
+            let tail = self.tail.load(SeqCst);
+            // Safety: nodes reachable from the list are not freed while pinned
+            let next = unsafe { (*head).next.load(SeqCst) };
+            if next.is_null() {
+                return None;
+            }
+            if head == tail {
+                let _ = self.tail.compare_exchange(tail, next, SeqCst, SeqCst);
+                continue;
+            }
+            if self
+                .head
+                .compare_exchange(head, next, SeqCst, SeqCst)
+                .is_ok()
+            {
+                // Safety: `next` is the new sentinel, so only the thread that
+                // unlinked `head` takes its value. `head` is no longer reachable
+                unsafe {
+                    let val = (*next).value.swap(ptr::null_mut(), SeqCst);
+                    self.domain.retire_node(py, head);
+                    return Some(val);
+                }
+            }
+        }
+    }
+
+    fn for_each(&self, mut f: impl FnMut(*mut ffi::PyObject)) {
+        let mut node = self.head.load(SeqCst);
+        while !node.is_null() {
+            // Safety: only called while no other thread can modify the list
+            let val = unsafe { (*node).value.load(SeqCst) };
+            if !val.is_null() {
+                f(val);
+            }
+            node = unsafe { (*node).next.load(SeqCst) };
+        }
+    }
+}
+
+impl Drop for List {
+    fn drop(&mut self) {
+        let mut node = *self.head.get_mut();
+        while !node.is_null() {
+            // Safety: nothing else can access the list anymore, and the GIL is held
+            let mut owned = unsafe { Box::from_raw(node) };
+            unsafe { ffi::Py_DecRef(*owned.value.get_mut()) };
+            node = *owned.next.get_mut();
+        }
+    }
+}
+
+#[derive(Debug)]
+enum Storage {
+    Bounded(Ring),
+    Unbounded(List),
+}
+
+/// A first-in first-out queue for any number of producers and consumers.
+#[pyclass(module = "haxe_atomic", frozen)]
+#[derive(Debug)]
+pub struct ConcurrentQueue {
+    storage: Storage,
+    // Updated after pushing and popping, so that it can be briefly off by the
+    // number of operations in progress
+    len: AtomicIsize,
+    closed: AtomicBool,
+    not_empty: Notifier,
+    not_full: Notifier,
+}
+
+#[pymethods]
+impl ConcurrentQueue {
+    #[new]
+    #[pyo3(signature = (capacity = None))]
+    fn new(capacity: Option<usize>) -> PyResult<Self> {
+        Ok(Self {
+            storage: match capacity {
+                Some(0) => return Err(PyValueError::new_err("capacity must be positive")),
+                Some(capacity) => Storage::Bounded(Ring::new(capacity)),
+                None => Storage::Unbounded(List::new()),
+            },
+            len: AtomicIsize::new(0),
+            closed: AtomicBool::new(false),
+            not_empty: Notifier::new(),
+            not_full: Notifier::new(),
+        })
+    }
+
+    #[getter]
+    fn capacity(&self) -> Option<usize> {
+        match &self.storage {
+            Storage::Bounded(ring) => Some(ring.cells.len()),
+            Storage::Unbounded(_) => None,
+        }
+    }
+
+    #[getter]
+    fn closed(&self) -> bool {
+        self.closed.load(SeqCst)
+    }
+
+    /// Append `item`, waiting for room for up to `timeout` seconds if the queue
+    /// is bounded and full. Returns false if it is still full by then.
+    #[pyo3(signature = (item, timeout = None))]
+    pub fn push(&self, item: Bound<'_, PyAny>, timeout: Option<f64>) -> PyResult<bool> {
+        let py = item.py();
+        let deadline = crate::wait::deadline(timeout)?;
+        let val = item.into_ptr();
+        loop {
+            match self.try_push_ptr(py, val) {
+                Ok(true) => return Ok(true),
+                Ok(false) => {}
+                Err(err) => {
+                    // Safety: `val` was not queued, so it is still owned here
+                    unsafe { ffi::Py_DecRef(val) };
+                    return Err(err);
+                }
+            }
+            let capacity = self.capacity().unwrap_or(usize::MAX);
+            let waited = self.not_full.wait_until(py, deadline, || {
+                self.__len__() >= capacity && !self.closed.load(SeqCst)
+            });
+            if !matches!(waited, Ok(true)) {
+                // Safety: as above
+                unsafe { ffi::Py_DecRef(val) };
+                return waited;
+            }
+        }
+    }
+
+    /// Append `item` if there is room, returning whether it was queued.
+    pub fn try_push(&self, item: Bound<'_, PyAny>) -> PyResult<bool> {
+        let py = item.py();
+        let val = item.into_ptr();
+        let res = self.try_push_ptr(py, val);
+        if !matches!(res, Ok(true)) {
+            // Safety: `val` was not queued, so it is still owned here
+            unsafe { ffi::Py_DecRef(val) };
+        }
+        res
+    }
+
+    /// Remove the oldest item, or return `default` if the queue is empty.
+    #[pyo3(signature = (default = None))]
+    pub fn try_pop(&self, py: Python<'_>, default: Option<Py<PyAny>>) -> Py<PyAny> {
+        self.pop_item(py)
+            .unwrap_or_else(|| default.unwrap_or_else(|| py.None()))
+    }
+
+    /// Remove the oldest item, waiting for up to `timeout` seconds for one.
+    ///
+    /// Raises `TimeoutError` if the queue is still empty by then, and
+    /// `QueueClosed` once it is closed and empty.
+    #[pyo3(signature = (timeout = None))]
+    pub fn pop(&self, py: Python<'_>, timeout: Option<f64>) -> PyResult<Py<PyAny>> {
+        let deadline = crate::wait::deadline(timeout)?;
+        loop {
+            if let Some(item) = self.pop_item(py) {
+                return Ok(item);
+            }
+            if self.closed.load(SeqCst) {
+                return Err(QueueClosed::new_err("queue is closed"));
+            }
+            let waited = self.not_empty.wait_until(py, deadline, || {
+                self.__len__() == 0 && !self.closed.load(SeqCst)
+            })?;
+            if !waited {
+                return Err(PyTimeoutError::new_err("queue is empty"));
+            }
+        }
+    }
+
+    /// Refuse further pushes and wake every waiting thread. Items already queued
+    /// can still be popped.
+    pub fn close(&self) {
+        self.closed.store(true, SeqCst);
+        self.not_empty.notify_all();
+        self.not_full.notify_all();
+    }
+
+    /// Number of queued items, which may be outdated by concurrent operations.
+    fn __len__(&self) -> usize {
+        self.len.load(SeqCst).max(0) as usize
+    }
+
+    fn __traverse__(&self, visit: PyVisit<'_>) -> Result<(), PyTraverseError> {
+        let mut res = Ok(());
+        // The following must use a method that does not increment the ref count
+        // otherwise the cycle collector may fail to detect cycles
+        let mut visit_ptr = |ptr| {
+            if res.is_ok() {
+                let object = ManuallyDrop::new(unsafe {
+                    Py::<PyAny>::from_owned_ptr(Python::assume_gil_acquired(), ptr)
+                });
+                res = visit.call(&*object);
+            }
+        };
+        match &self.storage {
+            Storage::Bounded(ring) => ring.for_each(&mut visit_ptr),
+            Storage::Unbounded(list) => list.for_each(&mut visit_ptr),
+        }
+        res
+    }
+
+    fn __clear__(&self) {
+        // Safety: the GIL is held
+        let py = unsafe { Python::assume_gil_acquired() };
+        while self.pop_item(py).is_some() {}
+    }
+}
+
+impl ConcurrentQueue {
+    // Queue the owned reference `val`, returning false if the queue is full, in
+    // which case the caller still owns `val`
+    fn try_push_ptr(&self, py: Python<'_>, val: *mut ffi::PyObject) -> PyResult<bool> {
+        if self.closed.load(SeqCst) {
+            return Err(QueueClosed::new_err("queue is closed"));
+        }
+        match &self.storage {
+            Storage::Bounded(ring) => {
+                if ring.push(val).is_err() {
+                    return Ok(false);
+                }
+            }
+            Storage::Unbounded(list) => list.push(py, val),
+        }
+        self.len.fetch_add(1, SeqCst);
+        self.not_empty.notify_one();
+        Ok(true)
+    }
+
+    fn pop_item(&self, py: Python<'_>) -> Option<Py<PyAny>> {
+        let val = match &self.storage {
+            Storage::Bounded(ring) => ring.pop(),
+            Storage::Unbounded(list) => list.pop(py),
+        }?;
+        self.len.fetch_sub(1, SeqCst);
+        self.not_full.notify_one();
+        // Safety: popped references are owned
+        Some(unsafe { Py::from_owned_ptr(py, val) })
+    }
+}
+
+impl Drop for ConcurrentQueue {
+    fn drop(&mut self) {
+        if let Storage::Bounded(ring) = &self.storage {
+            // Safety: nothing else can access the ring anymore, and the GIL is held
+            ring.for_each(|val| unsafe { ffi::Py_DecRef(val) });
+        }
+    }
+}
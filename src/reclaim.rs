@@ -0,0 +1,185 @@
+//! Deferred release of references that have been removed from an atomic slot.
+//!
+//! Without the GIL, one thread may read a pointer out of a slot and be about to
+//! increment its reference count while another thread replaces the slot's
+//! contents and drops the last reference to the old object. Readers therefore
+//! pin the slot's [`Domain`] while turning a raw pointer into an owned reference,
+//! and references removed from a slot are only released once every reader that
+//! was pinned at the time has unpinned. Pins are counted per epoch, so retired
+//! references wait for at most two epochs of readers, however many threads keep
+//! loading concurrently. Lock-free containers use the same scheme to free the
+//! nodes they unlink.
+
+use pyo3::{ffi, prelude::*};
+use std::sync::{
+    atomic::{AtomicPtr, AtomicUsize, Ordering},
+    Mutex, PoisonError,
+};
+
+enum Retired {
+    Object(*mut ffi::PyObject),
+    // Only held to be dropped
+    Node(#[allow(dead_code)] Box<dyn Send>),
+}
+
+// Safety: a retired pointer is an owned reference that is only released while attached to the interpreter
+unsafe impl Send for Retired {}
+
+impl Drop for Retired {
+    fn drop(&mut self) {
+        if let Retired::Object(ptr) = *self {
+            // Safety: retired references are owned and only dropped while attached to the interpreter
+            unsafe { ffi::Py_DecRef(ptr) };
+        }
+    }
+}
+
+impl std::fmt::Debug for Retired {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Retired::Object(ptr) => f.debug_tuple("Object").field(ptr).finish(),
+            Retired::Node(_) => f.write_str("Node"),
+        }
+    }
+}
+
+// Items retired while the epoch was `e` can be released once it reaches `e + 2`
+#[derive(Debug, Default)]
+pub struct Domain {
+    epoch: AtomicUsize,
+    // Pinned readers by the parity of the epoch they pinned in. The epoch only
+    // advances once the counter of the previous epoch has drained, so readers
+    // that keep pinning in the current one cannot hold back reclamation
+    readers: [AtomicUsize; 2],
+    pending: AtomicUsize,
+    retired: Mutex<Vec<(usize, Retired)>>,
+}
+
+impl Domain {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn pin<'py>(&self, py: Python<'py>) -> Guard<'_, 'py> {
+        loop {
+            let epoch = self.epoch.load(Ordering::SeqCst);
+            let readers = &self.readers[epoch % 2];
+            readers.fetch_add(1, Ordering::SeqCst);
+            // The epoch may have advanced past one with the same parity in between
+            if self.epoch.load(Ordering::SeqCst) == epoch {
+                return Guard {
+                    domain: self,
+                    readers,
+                    py,
+                };
+            }
+            readers.fetch_sub(1, Ordering::SeqCst);
+        }
+    }
+
+    /// Release the owned reference `ptr` once no reader can still observe it.
+    ///
+    /// # Safety
+    /// `ptr` must be null or an owned reference that is no longer reachable from
+    /// any slot protected by this domain.
+    pub unsafe fn retire(&self, py: Python, ptr: *mut ffi::PyObject) {
+        if !ptr.is_null() {
+            self.defer(py, Retired::Object(ptr));
+        }
+    }
+
+    /// Free `node`, allocated with `Box`, once no reader can still observe it.
+    ///
+    /// # Safety
+    /// `node` must no longer be reachable from the container protected by this domain.
+    pub unsafe fn retire_node<T: Send + 'static>(&self, py: Python, node: *mut T) {
+        self.defer(py, Retired::Node(Box::from_raw(node)));
+    }
+
+    fn defer(&self, _py: Python, item: Retired) {
+        if self.readers.iter().all(|r| r.load(Ordering::SeqCst) == 0) {
+            // Readers that pin from now on can only observe the new contents
+            drop(item);
+            return;
+        }
+        {
+            let mut retired = self.retired.lock().unwrap_or_else(PoisonError::into_inner);
+            retired.push((self.epoch.load(Ordering::SeqCst), item));
+            self.pending.store(retired.len(), Ordering::SeqCst);
+        }
+        // The last reader may have unpinned before seeing `pending`
+        self.collect();
+    }
+
+    // Advance the epoch as far as the pinned readers allow, returning the new one
+    fn advance(&self) -> usize {
+        let mut epoch = self.epoch.load(Ordering::SeqCst);
+        for _ in 0..2 {
+            if self.readers[epoch.wrapping_add(1) % 2].load(Ordering::SeqCst) != 0 {
+                break;
+            }
+            epoch = match self.epoch.compare_exchange(
+                epoch,
+                epoch.wrapping_add(1),
+                Ordering::SeqCst,
+                Ordering::SeqCst,
+            ) {
+                Ok(_) => epoch.wrapping_add(1),
+                Err(current) => current,
+            };
+        }
+        epoch
+    }
+
+    fn collect(&self) {
+        let batch = {
+            let mut retired = self.retired.lock().unwrap_or_else(PoisonError::into_inner);
+            let epoch = self.advance();
+            let (batch, kept) = std::mem::take(&mut *retired)
+                .into_iter()
+                .partition(|(retired, _)| epoch.wrapping_sub(*retired) >= 2);
+            *retired = kept;
+            self.pending.store(retired.len(), Ordering::SeqCst);
+            batch
+        };
+        // Releasing may run arbitrary python code, so it must happen outside the lock
+        drop::<Vec<_>>(batch);
+    }
+}
+
+pub struct Guard<'a, 'py> {
+    domain: &'a Domain,
+    readers: &'a AtomicUsize,
+    py: Python<'py>,
+}
+
+impl<'py> Guard<'_, 'py> {
+    /// Take a new reference to the object currently stored in `slot`, if any.
+    pub fn load(
+        &self,
+        slot: &AtomicPtr<ffi::PyObject>,
+        order: Ordering,
+    ) -> Option<Bound<'py, PyAny>> {
+        // Safety: the pointer is read while pinned
+        unsafe { self.protect(slot.load(order)) }
+    }
+
+    /// Take a new reference to `ptr`, such as the current value returned by a
+    /// failed exchange.
+    ///
+    /// # Safety
+    /// `ptr` must be null or have been read from a slot protected by this domain
+    /// after the guard was created, since it is only kept alive until the guard is dropped.
+    pub unsafe fn protect(&self, ptr: *mut ffi::PyObject) -> Option<Bound<'py, PyAny>> {
+        Bound::from_borrowed_ptr_or_opt(self.py, ptr)
+    }
+}
+
+impl Drop for Guard<'_, '_> {
+    fn drop(&mut self) {
+        self.readers.fetch_sub(1, Ordering::SeqCst);
+        if self.domain.pending.load(Ordering::SeqCst) != 0 {
+            self.domain.collect();
+        }
+    }
+}
@@ -0,0 +1,125 @@
+//! Atomics operating in place on memory owned by another python object, so that
+//! they can be shared with other processes through `mmap` or `SharedMemory`.
+//!
+//! The limited API has no access to the buffer protocol, so the memory is
+//! exported through a `ctypes` array, which keeps the buffer locked (unable to
+//! be resized or closed) for as long as the atomic holds on to it.
+
+use pyo3::{
+    exceptions::{PyTypeError, PyValueError},
+    prelude::*,
+};
+use std::{mem, ops::Deref, ptr::NonNull, sync::atomic};
+
+#[derive(Debug)]
+pub enum Storage<T> {
+    Inline(T),
+    Buffer { ptr: NonNull<T>, _export: Py<PyAny> },
+}
+
+// Safety: the memory behind `ptr` stays valid for as long as `_export` is alive
+// and is only ever accessed through `T`
+unsafe impl<T: Send + Sync> Send for Storage<T> {}
+unsafe impl<T: Send + Sync> Sync for Storage<T> {}
+
+impl<T> Deref for Storage<T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        match self {
+            Storage::Inline(val) => val,
+            // Safety: see above
+            Storage::Buffer { ptr, .. } => unsafe { ptr.as_ref() },
+        }
+    }
+}
+
+impl<T> Storage<T> {
+    /// Fail for atomics placed in a buffer, since a copy or a pickle of their
+    /// value would no longer share memory with anything.
+    pub fn check_detachable(&self) -> PyResult<()> {
+        match self {
+            Storage::Inline(_) => Ok(()),
+            Storage::Buffer { .. } => Err(PyTypeError::new_err(
+                "cannot copy or pickle an atomic placed in a buffer",
+            )),
+        }
+    }
+}
+
+pub trait FromBuffer: Sized {
+    /// View the memory at `offset` in the writable buffer `buf` as `Self`.
+    fn from_buffer(buf: &Bound<'_, PyAny>, offset: usize) -> PyResult<Storage<Self>>;
+}
+
+// Lock `size` bytes at `offset` in `buf`, returning their address and the object
+// keeping them alive
+fn export(
+    buf: &Bound<'_, PyAny>,
+    offset: usize,
+    size: usize,
+    align: usize,
+) -> PyResult<(NonNull<u8>, Py<PyAny>)> {
+    let py = buf.py();
+    // `SharedMemory` is not a buffer itself
+    let buf = if buf.hasattr("buf")? {
+        buf.getattr("buf")?
+    } else {
+        buf.clone()
+    };
+    let ctypes = py.import("ctypes")?;
+    // Raises for read-only buffers and out of bounds offsets
+    let array = ctypes
+        .getattr("c_char")?
+        .mul(size)?
+        .call_method1("from_buffer", (buf, offset))?;
+    let addr: usize = ctypes.getattr("addressof")?.call1((&array,))?.extract()?;
+    if !addr.is_multiple_of(align) {
+        return Err(PyValueError::new_err(format!(
+            "offset {offset} is not aligned to {align} bytes"
+        )));
+    }
+    let ptr = NonNull::new(addr as *mut u8)
+        .ok_or_else(|| PyValueError::new_err("buffer has no memory"))?;
+    Ok((ptr, array.unbind()))
+}
+
+macro_rules! from_buffer {
+    ($($atomic:ty),*) => {
+        $(
+            impl FromBuffer for $atomic {
+                fn from_buffer(buf: &Bound<'_, PyAny>, offset: usize) -> PyResult<Storage<Self>> {
+                    let (ptr, export) =
+                        export(buf, offset, mem::size_of::<Self>(), mem::align_of::<Self>())?;
+                    // Any bit pattern is a valid integer
+                    Ok(Storage::Buffer {
+                        ptr: ptr.cast(),
+                        _export: export,
+                    })
+                }
+            }
+        )*
+    };
+}
+
+from_buffer!(atomic::AtomicU8, atomic::AtomicI32, atomic::AtomicU32);
+#[cfg(target_has_atomic = "64")]
+from_buffer!(atomic::AtomicI64, atomic::AtomicU64);
+
+#[cfg(not(target_has_atomic = "64"))]
+macro_rules! unsupported {
+    ($($atomic:ty),*) => {
+        $(
+            impl FromBuffer for $atomic {
+                fn from_buffer(_buf: &Bound<'_, PyAny>, _offset: usize) -> PyResult<Storage<Self>> {
+                    Err(pyo3::exceptions::PyNotImplementedError::new_err(
+                        "64-bit atomics cannot be placed in a buffer on this platform",
+                    ))
+                }
+            }
+        )*
+    };
+}
+
+#[cfg(not(target_has_atomic = "64"))]
+unsupported!(crate::fallback::AtomicI64, crate::fallback::AtomicU64);
@@ -0,0 +1,177 @@
+//! Operations on a single atomic reference slot, shared by `AtomicObject` and
+//! `AtomicObjectArray`.
+//!
+//! The slot is always accessed with `SeqCst`, which releasing replaced
+//! references through the [`Domain`] relies on.
+
+use crate::{is_abort, reclaim::Domain, watch::Watchers};
+use pyo3::{ffi, prelude::*, PyTraverseError, PyVisit};
+use std::{
+    mem::ManuallyDrop,
+    ptr,
+    sync::atomic::{AtomicPtr, Ordering::SeqCst},
+};
+
+pub struct Slot<'a> {
+    // Invariant: contains an owned pointer to a valid python object, or null once cleared
+    value: &'a AtomicPtr<ffi::PyObject>,
+    // Owned pointers removed from `value` must be released through `domain`
+    domain: &'a Domain,
+    watchers: Option<&'a Watchers>,
+}
+
+impl<'a> Slot<'a> {
+    /// # Safety
+    /// `value` must uphold the invariant above and only ever have its contents
+    /// released through `domain`.
+    pub unsafe fn new(
+        value: &'a AtomicPtr<ffi::PyObject>,
+        domain: &'a Domain,
+        watchers: Option<&'a Watchers>,
+    ) -> Self {
+        Self {
+            value,
+            domain,
+            watchers,
+        }
+    }
+
+    pub fn load<'py>(&self, py: Python<'py>) -> Bound<'py, PyAny> {
+        self.domain
+            .pin(py)
+            .load(self.value, SeqCst)
+            .unwrap_or_else(|| py.None().into_bound(py))
+    }
+
+    /// Store `val`, returning the object it replaced.
+    pub fn swap<'py>(&self, val: Bound<'py, PyAny>) -> Bound<'py, PyAny> {
+        let py = val.py();
+        let old = self.value.swap(val.into_ptr(), SeqCst);
+        // Safety: `old` is owned by us until it is retired, so it is still alive here.
+        // The returned object gets its own reference, the one held by `self.value`
+        // is released once concurrent loads can no longer observe it
+        unsafe {
+            let ret = Bound::from_borrowed_ptr_or_opt(py, old)
+                .unwrap_or_else(|| py.None().into_bound(py));
+            self.domain.retire(py, old);
+            self.wake(py);
+            ret
+        }
+    }
+
+    // Replace the stored object with `desired` if it is `expected`, or compares equal to it
+    // unless `identity` is set. A weak exchange gives up as soon as another thread modifies
+    // the slot in between
+    pub fn compare_exchange<'py>(
+        &self,
+        expected: &Bound<'py, PyAny>,
+        desired: &Bound<'py, PyAny>,
+        identity: bool,
+        weak: bool,
+    ) -> PyResult<(bool, Bound<'py, PyAny>)> {
+        let py = expected.py();
+        loop {
+            let orig = if identity {
+                expected.clone()
+            } else {
+                let orig = self.domain.pin(py).load(self.value, SeqCst);
+                match orig {
+                    Some(orig) if orig.eq(expected)? => orig,
+                    orig => return Ok((false, orig.unwrap_or_else(|| py.None().into_bound(py)))),
+                }
+            };
+            // Stay pinned so that the object the exchange failed on can be returned,
+            // rather than whatever the slot contains by the time it is read again
+            let guard = self.domain.pin(py);
+            match self.replace(py, orig.as_ptr(), desired, weak) {
+                Ok(()) => return Ok((true, orig)),
+                Err(actual) if identity || weak => {
+                    // Safety: `actual` was read from the slot while pinned
+                    let actual = unsafe { guard.protect(actual) };
+                    return Ok((false, actual.unwrap_or_else(|| py.None().into_bound(py))));
+                }
+                Err(_) => {}
+            }
+        }
+    }
+
+    // Apply `f` to the stored object until the result can be stored without another thread
+    // modifying the slot in between, or until `f` returns `ABORT`
+    pub fn fetch_update<'py>(
+        &self,
+        f: &Bound<'py, PyAny>,
+    ) -> PyResult<(Bound<'py, PyAny>, Bound<'py, PyAny>)> {
+        let py = f.py();
+        loop {
+            let prev = self.domain.pin(py).load(self.value, SeqCst);
+            let prev_ptr = prev.as_ref().map_or(ptr::null_mut(), Bound::as_ptr);
+            let prev = prev.unwrap_or_else(|| py.None().into_bound(py));
+            let next = f.call1((&prev,))?;
+            if is_abort(&next)? {
+                return Ok((prev.clone(), prev));
+            }
+            if self.replace(py, prev_ptr, &next, true).is_ok() {
+                return Ok((prev, next));
+            }
+        }
+    }
+
+    // Store `desired` if the slot still contains `current` and release the replaced reference,
+    // or return the pointer found in the slot instead
+    fn replace(
+        &self,
+        py: Python,
+        current: *mut ffi::PyObject,
+        desired: &Bound<PyAny>,
+        weak: bool,
+    ) -> Result<(), *mut ffi::PyObject> {
+        // Take the reference for `self.value` up front so that `desired` can never
+        // be observed in the slot without one
+        let desired_ptr = desired.clone().into_ptr();
+        let res = if weak {
+            self.value
+                .compare_exchange_weak(current, desired_ptr, SeqCst, SeqCst)
+        } else {
+            self.value
+                .compare_exchange(current, desired_ptr, SeqCst, SeqCst)
+        };
+        match res {
+            Ok(old) => {
+                // Safety: `old` was owned by `self.value`
+                unsafe { self.domain.retire(py, old) };
+                self.wake(py);
+                Ok(())
+            }
+            Err(actual) => {
+                // Safety: `desired_ptr` was not stored and `desired` still holds a reference
+                unsafe { ffi::Py_DecRef(desired_ptr) };
+                Err(actual)
+            }
+        }
+    }
+
+    fn wake(&self, py: Python<'_>) {
+        if let Some(watchers) = self.watchers {
+            watchers.wake(py, SeqCst);
+        }
+    }
+
+    pub fn traverse(&self, visit: &PyVisit<'_>) -> Result<(), PyTraverseError> {
+        // The following must use a method that does not increment the ref count
+        // otherwise the cycle collector may fail to detect cycles
+        let object = ManuallyDrop::new(unsafe {
+            Py::<PyAny>::from_owned_ptr_or_opt(
+                Python::assume_gil_acquired(),
+                self.value.load(SeqCst),
+            )
+        });
+        visit.call(&*object)
+    }
+
+    pub fn clear(&self) {
+        // Clear reference and decrement its ref counter once no load can observe it
+        let ptr = self.value.swap(ptr::null_mut(), SeqCst);
+        // Safety: the GIL is held and `ptr` is either owned or null
+        unsafe { self.domain.retire(Python::assume_gil_acquired(), ptr) };
+    }
+}
@@ -0,0 +1,347 @@
+//! Ring buffers for exactly one producing and one consuming thread.
+//!
+//! Each side only writes its own position and caches the other side's, so the
+//! common case touches no shared cache line. A busy flag per side turns a second
+//! thread pushing or popping concurrently into an exception instead of
+//! corrupting the ring.
+
+use crate::padded::CachePadded;
+use pyo3::{
+    exceptions::{PyRuntimeError, PyValueError},
+    ffi,
+    prelude::*,
+    types::PyBytes,
+    PyTraverseError, PyVisit,
+};
+use std::{
+    borrow::Cow,
+    cell::UnsafeCell,
+    mem::ManuallyDrop,
+    ptr,
+    sync::atomic::{
+        AtomicBool, AtomicPtr, AtomicUsize,
+        Ordering::{Acquire, Relaxed, Release},
+    },
+};
+
+#[derive(Debug, Default)]
+struct End {
+    // Number of items this side has pushed or popped
+    pos: AtomicUsize,
+    // Last seen position of the other side, only accessed by this side
+    cached: AtomicUsize,
+    busy: AtomicBool,
+}
+
+struct Busy<'a>(&'a AtomicBool);
+
+impl Drop for Busy<'_> {
+    fn drop(&mut self) {
+        self.0.store(false, Release);
+    }
+}
+
+#[derive(Debug)]
+struct Indices {
+    capacity: usize,
+    producer: CachePadded<End>,
+    consumer: CachePadded<End>,
+}
+
+impl Indices {
+    fn new(capacity: usize) -> PyResult<Box<Self>> {
+        if capacity == 0 {
+            return Err(PyValueError::new_err("capacity must be positive"));
+        }
+        Ok(Box::new(Self {
+            capacity,
+            producer: CachePadded::default(),
+            consumer: CachePadded::default(),
+        }))
+    }
+
+    fn claim<'a>(end: &'a End, action: &str) -> PyResult<Busy<'a>> {
+        if end.busy.swap(true, Acquire) {
+            return Err(PyRuntimeError::new_err(format!(
+                "another thread is already {action} this ring"
+            )));
+        }
+        Ok(Busy(&end.busy))
+    }
+
+    // Let `write` fill up to `max` free slots starting at the position it is
+    // passed, returning how many it was given
+    fn produce(&self, max: usize, write: impl FnOnce(usize, usize)) -> PyResult<usize> {
+        let _busy = Self::claim(&self.producer, "pushing to")?;
+        let tail = self.producer.pos.load(Relaxed);
+        let mut head = self.producer.cached.load(Relaxed);
+        if self.capacity - tail.wrapping_sub(head) < max {
+            head = self.consumer.pos.load(Acquire);
+            self.producer.cached.store(head, Relaxed);
+        }
+        let n = max.min(self.capacity - tail.wrapping_sub(head));
+        if n != 0 {
+            write(tail, n);
+            self.producer.pos.store(tail.wrapping_add(n), Release);
+        }
+        Ok(n)
+    }
+
+    // Let `read` take up to `max` filled slots starting at the position it is
+    // passed, returning how many it was given
+    fn consume(&self, max: usize, read: impl FnOnce(usize, usize)) -> PyResult<usize> {
+        let _busy = Self::claim(&self.consumer, "popping from")?;
+        let head = self.consumer.pos.load(Relaxed);
+        let mut tail = self.consumer.cached.load(Relaxed);
+        if tail.wrapping_sub(head) < max {
+            tail = self.producer.pos.load(Acquire);
+            self.consumer.cached.store(tail, Relaxed);
+        }
+        let n = max.min(tail.wrapping_sub(head));
+        if n != 0 {
+            read(head, n);
+            self.consumer.pos.store(head.wrapping_add(n), Release);
+        }
+        Ok(n)
+    }
+
+    fn len(&self) -> usize {
+        let head = self.consumer.pos.load(Acquire);
+        let tail = self.producer.pos.load(Acquire);
+        tail.wrapping_sub(head).min(self.capacity)
+    }
+}
+
+/// A bounded queue of python objects for one producer and one consumer thread.
+#[pyclass(module = "haxe_atomic", frozen)]
+#[derive(Debug)]
+pub struct SpscRing {
+    // Boxed since python does not allocate objects with cache line alignment
+    indices: Box<Indices>,
+    // Owned references between the consumer and producer positions, null elsewhere
+    slots: Box<[AtomicPtr<ffi::PyObject>]>,
+}
+
+#[pymethods]
+impl SpscRing {
+    #[new]
+    fn new(capacity: usize) -> PyResult<Self> {
+        Ok(Self {
+            indices: Indices::new(capacity)?,
+            slots: (0..capacity)
+                .map(|_| AtomicPtr::new(ptr::null_mut()))
+                .collect(),
+        })
+    }
+
+    #[getter]
+    fn capacity(&self) -> usize {
+        self.indices.capacity
+    }
+
+    /// Append `item`, returning false if the ring is full.
+    pub fn push(&self, item: Bound<'_, PyAny>) -> PyResult<bool> {
+        Ok(self.push_many(vec![item])? == 1)
+    }
+
+    /// Append as many of `items` as fit, returning how many were appended.
+    pub fn push_many(&self, items: Vec<Bound<'_, PyAny>>) -> PyResult<usize> {
+        let mut items = items.into_iter();
+        self.indices.produce(items.len(), |start, n| {
+            for (pos, item) in (start..).take(n).zip(&mut items) {
+                self.slot(pos).store(item.into_ptr(), Relaxed);
+            }
+        })
+    }
+
+    /// Remove the oldest item, or return `default` if the ring is empty.
+    #[pyo3(signature = (default = None))]
+    pub fn pop(&self, py: Python<'_>, default: Option<Py<PyAny>>) -> PyResult<Py<PyAny>> {
+        Ok(match self.pop_many(py, Some(1))?.pop() {
+            Some(item) => item,
+            None => default.unwrap_or_else(|| py.None()),
+        })
+    }
+
+    /// Remove up to `max_items` of the oldest items, or all of them.
+    #[pyo3(signature = (max_items = None))]
+    pub fn pop_many(&self, py: Python<'_>, max_items: Option<usize>) -> PyResult<Vec<Py<PyAny>>> {
+        let mut items = Vec::new();
+        self.indices
+            .consume(max_items.unwrap_or(usize::MAX), |start, n| {
+                items.reserve(n);
+                for pos in (start..).take(n) {
+                    let ptr = self.slot(pos).swap(ptr::null_mut(), Relaxed);
+                    // Safety: slots between the positions hold owned references
+                    items.push(unsafe { Py::from_owned_ptr(py, ptr) });
+                }
+            })?;
+        Ok(items)
+    }
+
+    /// Number of queued items, which may be outdated by the other thread.
+    fn __len__(&self) -> usize {
+        self.indices.len()
+    }
+
+    fn __traverse__(&self, visit: PyVisit<'_>) -> Result<(), PyTraverseError> {
+        for slot in self.slots.iter() {
+            // The following must use a method that does not increment the ref count
+            // otherwise the cycle collector may fail to detect cycles
+            let object = ManuallyDrop::new(unsafe {
+                Py::<PyAny>::from_owned_ptr_or_opt(
+                    Python::assume_gil_acquired(),
+                    slot.load(Relaxed),
+                )
+            });
+            visit.call(&*object)?;
+        }
+        Ok(())
+    }
+
+    fn __clear__(&self) {
+        // Safety: the GIL is held
+        let py = unsafe { Python::assume_gil_acquired() };
+        let _ = self.pop_many(py, None);
+    }
+}
+
+impl SpscRing {
+    fn slot(&self, pos: usize) -> &AtomicPtr<ffi::PyObject> {
+        &self.slots[pos % self.slots.len()]
+    }
+}
+
+impl Drop for SpscRing {
+    fn drop(&mut self) {
+        for slot in self.slots.iter_mut() {
+            // Safety: nothing else can access the slots anymore, and the GIL is held
+            unsafe { ffi::Py_DecRef(*slot.get_mut()) };
+        }
+    }
+}
+
+/// A bounded queue of fixed-size byte records for one producer and one consumer
+/// thread, copied in and out of a single buffer.
+#[pyclass(module = "haxe_atomic", frozen)]
+pub struct SpscByteRing {
+    indices: Box<Indices>,
+    record_size: usize,
+    // Records between the consumer and producer positions are only accessed by
+    // the consumer, the others only by the producer
+    buf: Box<[UnsafeCell<u8>]>,
+}
+
+// Safety: see `buf`, which `Indices` enforces by allowing only one thread per side
+unsafe impl Sync for SpscByteRing {}
+
+#[pymethods]
+impl SpscByteRing {
+    #[new]
+    fn new(capacity: usize, record_size: usize) -> PyResult<Self> {
+        if record_size == 0 {
+            return Err(PyValueError::new_err("record_size must be positive"));
+        }
+        let size = capacity
+            .checked_mul(record_size)
+            .ok_or_else(|| PyValueError::new_err("ring is too large"))?;
+        Ok(Self {
+            indices: Indices::new(capacity)?,
+            record_size,
+            buf: (0..size).map(|_| UnsafeCell::new(0)).collect(),
+        })
+    }
+
+    #[getter]
+    fn capacity(&self) -> usize {
+        self.indices.capacity
+    }
+
+    #[getter]
+    fn record_size(&self) -> usize {
+        self.record_size
+    }
+
+    /// Append the record `data`, returning false if the ring is full.
+    pub fn push(&self, data: Cow<'_, [u8]>) -> PyResult<bool> {
+        if data.len() != self.record_size {
+            return Err(PyValueError::new_err(format!(
+                "record must be {} bytes, not {}",
+                self.record_size,
+                data.len()
+            )));
+        }
+        Ok(self.push_many(data)? == 1)
+    }
+
+    /// Append as many of the records concatenated in `data` as fit, returning how
+    /// many were appended.
+    pub fn push_many(&self, data: Cow<'_, [u8]>) -> PyResult<usize> {
+        if !data.len().is_multiple_of(self.record_size) {
+            return Err(PyValueError::new_err(format!(
+                "data length {} is not a multiple of the record size {}",
+                data.len(),
+                self.record_size
+            )));
+        }
+        self.indices
+            .produce(data.len() / self.record_size, |start, n| {
+                // Safety: the producer owns the free records
+                unsafe { self.copy(start, n, data.as_ptr(), true) }
+            })
+    }
+
+    /// Remove the oldest record, or return `None` if the ring is empty.
+    pub fn pop<'py>(&self, py: Python<'py>) -> PyResult<Option<Bound<'py, PyBytes>>> {
+        let records = self.pop_many(py, Some(1))?;
+        Ok((!records.as_bytes().is_empty()).then_some(records))
+    }
+
+    /// Remove up to `max_records` of the oldest records, or all of them, and
+    /// return them concatenated.
+    #[pyo3(signature = (max_records = None))]
+    pub fn pop_many<'py>(
+        &self,
+        py: Python<'py>,
+        max_records: Option<usize>,
+    ) -> PyResult<Bound<'py, PyBytes>> {
+        let mut data = Vec::new();
+        self.indices
+            .consume(max_records.unwrap_or(usize::MAX), |start, n| {
+                data.resize(n * self.record_size, 0);
+                // Safety: the consumer owns the filled records
+                unsafe { self.copy(start, n, data.as_mut_ptr(), false) }
+            })?;
+        Ok(PyBytes::new(py, &data))
+    }
+
+    fn __len__(&self) -> usize {
+        self.indices.len()
+    }
+}
+
+impl SpscByteRing {
+    // Copy `n` records starting at position `start` from `data` into the ring,
+    // or out of it into `data`, wrapping around its end
+    //
+    // Safety: the records must belong to the calling side and `data` must be
+    // valid for `n` records
+    unsafe fn copy(&self, start: usize, n: usize, data: *const u8, into: bool) {
+        let capacity = self.indices.capacity;
+        let first = start % capacity;
+        let head = n.min(capacity - first);
+        for (record, count, offset) in [(first, head, 0), (0, n - head, head)] {
+            if count == 0 {
+                continue;
+            }
+            let ring = UnsafeCell::raw_get(self.buf.as_ptr().add(record * self.record_size));
+            let data = data.add(offset * self.record_size).cast_mut();
+            let len = count * self.record_size;
+            if into {
+                ptr::copy_nonoverlapping(data, ring, len);
+            } else {
+                ptr::copy_nonoverlapping(ring, data, len);
+            }
+        }
+    }
+}
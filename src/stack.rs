@@ -0,0 +1,153 @@
+//! A last-in first-out stack of python objects after R. Kent Treiber's design.
+//!
+//! Popped nodes are freed through a [`Domain`], so a node cannot be reused while
+//! another thread still compares against its address, which rules out ABA. A
+//! node also keeps its reference to the value until it is freed, since a thread
+//! peeking at it may still be about to take a new reference.
+
+use crate::reclaim::Domain;
+use pyo3::{ffi, prelude::*, PyTraverseError, PyVisit};
+use std::{
+    mem::ManuallyDrop,
+    ptr,
+    sync::atomic::{AtomicPtr, Ordering::SeqCst},
+};
+
+#[derive(Debug)]
+struct Node {
+    // Owned reference, released when the node is freed
+    value: AtomicPtr<ffi::PyObject>,
+    next: AtomicPtr<Node>,
+}
+
+impl Drop for Node {
+    fn drop(&mut self) {
+        // Safety: nodes are only freed while attached to the interpreter
+        unsafe { ffi::Py_DecRef(*self.value.get_mut()) };
+    }
+}
+
+/// A last-in first-out stack for any number of threads.
+#[pyclass(module = "haxe_atomic", frozen)]
+#[derive(Debug)]
+pub struct AtomicStack {
+    // Nodes unlinked from the top must be freed through `domain`
+    top: AtomicPtr<Node>,
+    domain: Domain,
+}
+
+#[pymethods]
+impl AtomicStack {
+    #[new]
+    fn new() -> Self {
+        Self {
+            top: AtomicPtr::new(ptr::null_mut()),
+            domain: Domain::new(),
+        }
+    }
+
+    pub fn push(&self, item: Bound<'_, PyAny>) {
+        let node = Box::into_raw(Box::new(Node {
+            value: AtomicPtr::new(item.into_ptr()),
+            next: AtomicPtr::new(ptr::null_mut()),
+        }));
+        let mut top = self.top.load(SeqCst);
+        loop {
+            // Safety: `node` is not shared until the exchange succeeds
+            unsafe { (*node).next.store(top, SeqCst) };
+            match self.top.compare_exchange(top, node, SeqCst, SeqCst) {
+                Ok(_) => return,
+                Err(current) => top = current,
+            }
+        }
+    }
+
+    /// Remove the top item, or return `default` if the stack is empty.
+    #[pyo3(signature = (default = None))]
+    pub fn pop(&self, py: Python<'_>, default: Option<Py<PyAny>>) -> Py<PyAny> {
+        let _guard = self.domain.pin(py);
+        let mut top = self.top.load(SeqCst);
+        while !top.is_null() {
+            // Safety: nodes reachable from the stack are not freed while pinned
+            let next = unsafe { (*top).next.load(SeqCst) };
+            match self.top.compare_exchange(top, next, SeqCst, SeqCst) {
+                // Safety: `top` is no longer reachable, and its value lives as long as it
+                Ok(_) => unsafe {
+                    let item = Py::from_borrowed_ptr(py, (*top).value.load(SeqCst));
+                    self.domain.retire_node(py, top);
+                    return item;
+                },
+                Err(current) => top = current,
+            }
+        }
+        default.unwrap_or_else(|| py.None())
+    }
+
+    /// Return the top item without removing it, or `default` if the stack is empty.
+    #[pyo3(signature = (default = None))]
+    pub fn peek(&self, py: Python<'_>, default: Option<Py<PyAny>>) -> Py<PyAny> {
+        let _guard = self.domain.pin(py);
+        let top = self.top.load(SeqCst);
+        if top.is_null() {
+            return default.unwrap_or_else(|| py.None());
+        }
+        // Safety: as in `pop`, the node and its value are not freed while pinned
+        unsafe { Py::from_borrowed_ptr(py, (*top).value.load(SeqCst)) }
+    }
+
+    /// Remove every item at once, returning them from top to bottom.
+    pub fn drain(&self, py: Python<'_>) -> Vec<Py<PyAny>> {
+        let _guard = self.domain.pin(py);
+        let mut node = self.top.swap(ptr::null_mut(), SeqCst);
+        let mut items = Vec::new();
+        while !node.is_null() {
+            // Safety: the detached nodes are only still visible to pinned threads
+            unsafe {
+                items.push(Py::from_borrowed_ptr(py, (*node).value.load(SeqCst)));
+                let next = (*node).next.load(SeqCst);
+                self.domain.retire_node(py, node);
+                node = next;
+            }
+        }
+        items
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.top.load(SeqCst).is_null()
+    }
+
+    fn __traverse__(&self, visit: PyVisit<'_>) -> Result<(), PyTraverseError> {
+        let mut node = self.top.load(SeqCst);
+        while !node.is_null() {
+            // The following must use a method that does not increment the ref count
+            // otherwise the cycle collector may fail to detect cycles.
+            // Safety: the stack is not modified while the collector runs
+            let object = ManuallyDrop::new(unsafe {
+                Py::<PyAny>::from_owned_ptr(
+                    Python::assume_gil_acquired(),
+                    (*node).value.load(SeqCst),
+                )
+            });
+            visit.call(&*object)?;
+            node = unsafe { (*node).next.load(SeqCst) };
+        }
+        Ok(())
+    }
+
+    fn __clear__(&self) {
+        // Safety: the GIL is held
+        let py = unsafe { Python::assume_gil_acquired() };
+        self.drain(py);
+    }
+}
+
+impl Drop for AtomicStack {
+    fn drop(&mut self) {
+        let mut node = *self.top.get_mut();
+        while !node.is_null() {
+            // Safety: nothing else can access the stack anymore, and the GIL is held
+            let mut owned = unsafe { Box::from_raw(node) };
+            node = *owned.next.get_mut();
+        }
+    }
+}
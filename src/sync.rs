@@ -0,0 +1,498 @@
+//! Blocking synchronization primitives with the semantics of Haxe's `sys.thread`
+//! classes.
+//!
+//! Threads release the GIL while blocked and wake up periodically to run python
+//! signal handlers, so that a blocked acquire can be interrupted.
+
+use crate::wait;
+use pyo3::{exceptions::PyRuntimeError, prelude::*};
+use std::{
+    collections::VecDeque,
+    sync::{
+        atomic::{AtomicBool, Ordering::SeqCst},
+        Arc, Condvar, Mutex as StdMutex, MutexGuard, PoisonError,
+    },
+    thread::{self, ThreadId},
+    time::Instant,
+};
+
+pub fn lock<T>(state: &StdMutex<T>) -> MutexGuard<'_, T> {
+    state.lock().unwrap_or_else(PoisonError::into_inner)
+}
+
+// Block until `acquire` succeeds in updating `state`, which is retried whenever
+// `cond` is notified. Returns false if `deadline` passes first
+pub fn block<T: Send>(
+    py: Python<'_>,
+    state: &StdMutex<T>,
+    cond: &Condvar,
+    deadline: Option<Instant>,
+    mut acquire: impl FnMut(&mut T) -> bool + Send,
+) -> PyResult<bool> {
+    if acquire(&mut lock(state)) {
+        return Ok(true);
+    }
+    loop {
+        let Some(slice) = wait::next_slice(deadline) else {
+            return Ok(false);
+        };
+        let acquired = py.allow_threads(|| {
+            let mut guard = lock(state);
+            if acquire(&mut guard) {
+                return true;
+            }
+            let (mut guard, _) = cond
+                .wait_timeout(guard, slice)
+                .unwrap_or_else(PoisonError::into_inner);
+            acquire(&mut guard)
+        });
+        if acquired {
+            return Ok(true);
+        }
+        py.check_signals()?;
+    }
+}
+
+#[derive(Debug, Default)]
+struct Owner {
+    thread: Option<ThreadId>,
+    // Number of times the owning thread acquired the mutex
+    count: usize,
+}
+
+/// A recursive mutex, which the owning thread must release as many times as it
+/// acquired it.
+#[pyclass(module = "haxe_atomic", frozen)]
+#[derive(Debug, Default)]
+pub struct Mutex {
+    owner: StdMutex<Owner>,
+    released: Condvar,
+}
+
+#[pymethods]
+impl Mutex {
+    #[new]
+    fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn acquire(&self, py: Python<'_>) -> PyResult<()> {
+        self.acquire_until(py, None)?;
+        Ok(())
+    }
+
+    #[pyo3(signature = (timeout = 0.0))]
+    pub fn try_acquire(&self, py: Python<'_>, timeout: f64) -> PyResult<bool> {
+        self.acquire_until(py, wait::deadline(Some(timeout))?)
+    }
+
+    pub fn release(&self) -> PyResult<()> {
+        let mut owner = lock(&self.owner);
+        if owner.thread != Some(thread::current().id()) {
+            return Err(PyRuntimeError::new_err("cannot release un-acquired mutex"));
+        }
+        owner.count -= 1;
+        if owner.count == 0 {
+            owner.thread = None;
+            drop(owner);
+            self.released.notify_one();
+        }
+        Ok(())
+    }
+
+    fn __enter__(slf: PyRef<'_, Self>) -> PyResult<PyRef<'_, Self>> {
+        slf.acquire(slf.py())?;
+        Ok(slf)
+    }
+
+    fn __exit__(
+        &self,
+        _exc_type: &Bound<'_, PyAny>,
+        _exc: &Bound<'_, PyAny>,
+        _tb: &Bound<'_, PyAny>,
+    ) -> PyResult<()> {
+        self.release()
+    }
+}
+
+impl Mutex {
+    fn acquire_until(&self, py: Python<'_>, deadline: Option<Instant>) -> PyResult<bool> {
+        let me = thread::current().id();
+        block(py, &self.owner, &self.released, deadline, |owner| {
+            match owner.thread {
+                Some(thread) if thread != me => return false,
+                _ => owner.thread = Some(me),
+            }
+            owner.count += 1;
+            true
+        })
+    }
+
+    // Release the mutex entirely, returning how many times it was acquired
+    fn release_all(&self) -> PyResult<usize> {
+        let mut owner = lock(&self.owner);
+        if owner.thread != Some(thread::current().id()) {
+            return Err(PyRuntimeError::new_err("cannot wait on un-acquired mutex"));
+        }
+        let count = std::mem::take(&mut owner.count);
+        owner.thread = None;
+        drop(owner);
+        self.released.notify_one();
+        Ok(count)
+    }
+
+    // Acquire the mutex `count` times again after `release_all`. This cannot be
+    // interrupted, so that the caller always holds the mutex again when an
+    // exception propagates
+    fn restore(&self, py: Python<'_>, count: usize) {
+        let me = thread::current().id();
+        py.allow_threads(|| {
+            let mut owner = lock(&self.owner);
+            while owner.thread.is_some() {
+                owner = self
+                    .released
+                    .wait(owner)
+                    .unwrap_or_else(PoisonError::into_inner);
+            }
+            owner.thread = Some(me);
+            owner.count = count;
+        });
+    }
+
+    fn check_owned(&self, action: &str) -> PyResult<()> {
+        if lock(&self.owner).thread != Some(thread::current().id()) {
+            return Err(PyRuntimeError::new_err(format!(
+                "cannot {action} on un-acquired mutex"
+            )));
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Default)]
+struct Access {
+    readers: usize,
+    writer: Option<ThreadId>,
+    // Readers wait for writers that are waiting, so that writers are not starved
+    waiting_writers: usize,
+}
+
+/// A readers-writer lock preferring writers, which is not recursive.
+#[pyclass(module = "haxe_atomic", frozen)]
+#[derive(Debug, Default)]
+pub struct RwLock {
+    access: StdMutex<Access>,
+    changed: Condvar,
+}
+
+#[pymethods]
+impl RwLock {
+    #[new]
+    fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn acquire_read(&self, py: Python<'_>) -> PyResult<()> {
+        self.acquire_read_until(py, None)?;
+        Ok(())
+    }
+
+    #[pyo3(signature = (timeout = 0.0))]
+    pub fn try_acquire_read(&self, py: Python<'_>, timeout: f64) -> PyResult<bool> {
+        self.acquire_read_until(py, wait::deadline(Some(timeout))?)
+    }
+
+    pub fn release_read(&self) -> PyResult<()> {
+        let mut access = lock(&self.access);
+        if access.readers == 0 {
+            return Err(PyRuntimeError::new_err(
+                "cannot release un-acquired read lock",
+            ));
+        }
+        access.readers -= 1;
+        if access.readers == 0 {
+            drop(access);
+            self.changed.notify_all();
+        }
+        Ok(())
+    }
+
+    pub fn acquire_write(&self, py: Python<'_>) -> PyResult<()> {
+        self.acquire_write_until(py, None)?;
+        Ok(())
+    }
+
+    #[pyo3(signature = (timeout = 0.0))]
+    pub fn try_acquire_write(&self, py: Python<'_>, timeout: f64) -> PyResult<bool> {
+        self.acquire_write_until(py, wait::deadline(Some(timeout))?)
+    }
+
+    pub fn release_write(&self) -> PyResult<()> {
+        let mut access = lock(&self.access);
+        if access.writer != Some(thread::current().id()) {
+            return Err(PyRuntimeError::new_err(
+                "cannot release un-acquired write lock",
+            ));
+        }
+        access.writer = None;
+        drop(access);
+        self.changed.notify_all();
+        Ok(())
+    }
+
+    /// Context manager holding the lock for reading.
+    fn read(slf: Py<Self>) -> RwLockGuard {
+        RwLockGuard {
+            lock: slf,
+            write: false,
+        }
+    }
+
+    /// Context manager holding the lock for writing.
+    fn write(slf: Py<Self>) -> RwLockGuard {
+        RwLockGuard {
+            lock: slf,
+            write: true,
+        }
+    }
+}
+
+impl RwLock {
+    fn acquire_read_until(&self, py: Python<'_>, deadline: Option<Instant>) -> PyResult<bool> {
+        block(py, &self.access, &self.changed, deadline, |access| {
+            if access.writer.is_some() || access.waiting_writers != 0 {
+                return false;
+            }
+            access.readers += 1;
+            true
+        })
+    }
+
+    fn acquire_write_until(&self, py: Python<'_>, deadline: Option<Instant>) -> PyResult<bool> {
+        let me = thread::current().id();
+        lock(&self.access).waiting_writers += 1;
+        let res = block(py, &self.access, &self.changed, deadline, |access| {
+            if access.writer.is_some() || access.readers != 0 {
+                return false;
+            }
+            access.writer = Some(me);
+            true
+        });
+        let mut access = lock(&self.access);
+        access.waiting_writers -= 1;
+        if access.waiting_writers == 0 && !matches!(res, Ok(true)) {
+            // Readers may have been waiting for this writer only
+            drop(access);
+            self.changed.notify_all();
+        }
+        res
+    }
+}
+
+#[pyclass(module = "haxe_atomic", frozen)]
+pub struct RwLockGuard {
+    lock: Py<RwLock>,
+    write: bool,
+}
+
+#[pymethods]
+impl RwLockGuard {
+    fn __enter__(&self, py: Python<'_>) -> PyResult<()> {
+        let lock = self.lock.get();
+        if self.write {
+            lock.acquire_write(py)
+        } else {
+            lock.acquire_read(py)
+        }
+    }
+
+    fn __exit__(
+        &self,
+        _exc_type: &Bound<'_, PyAny>,
+        _exc: &Bound<'_, PyAny>,
+        _tb: &Bound<'_, PyAny>,
+    ) -> PyResult<()> {
+        let lock = self.lock.get();
+        if self.write {
+            lock.release_write()
+        } else {
+            lock.release_read()
+        }
+    }
+}
+
+/// A condition variable bound to a `Mutex`, waking waiters in the order they
+/// started waiting.
+#[pyclass(module = "haxe_atomic", frozen)]
+pub struct Condition {
+    mutex: Py<Mutex>,
+    // Flags of the threads currently waiting, set when they are signalled
+    waiters: StdMutex<VecDeque<Arc<AtomicBool>>>,
+    signalled: Condvar,
+}
+
+#[pymethods]
+impl Condition {
+    #[new]
+    #[pyo3(signature = (mutex = None))]
+    fn new(py: Python<'_>, mutex: Option<Py<Mutex>>) -> PyResult<Self> {
+        Ok(Self {
+            mutex: match mutex {
+                Some(mutex) => mutex,
+                None => Py::new(py, Mutex::new())?,
+            },
+            waiters: StdMutex::default(),
+            signalled: Condvar::new(),
+        })
+    }
+
+    #[getter]
+    fn mutex(&self, py: Python<'_>) -> Py<Mutex> {
+        self.mutex.clone_ref(py)
+    }
+
+    pub fn acquire(&self, py: Python<'_>) -> PyResult<()> {
+        self.mutex.get().acquire(py)
+    }
+
+    #[pyo3(signature = (timeout = 0.0))]
+    pub fn try_acquire(&self, py: Python<'_>, timeout: f64) -> PyResult<bool> {
+        self.mutex.get().try_acquire(py, timeout)
+    }
+
+    pub fn release(&self) -> PyResult<()> {
+        self.mutex.get().release()
+    }
+
+    /// Release the mutex until signalled, returning false if `timeout` seconds
+    /// elapse first. The mutex is held again when this returns or raises.
+    #[pyo3(signature = (timeout = None))]
+    pub fn wait(&self, py: Python<'_>, timeout: Option<f64>) -> PyResult<bool> {
+        let deadline = wait::deadline(timeout)?;
+        let mutex = self.mutex.get();
+        let flag = Arc::new(AtomicBool::new(false));
+        // Queue up before releasing the mutex, a signal sent right after must not be missed
+        mutex.check_owned("wait")?;
+        lock(&self.waiters).push_back(flag.clone());
+        let count = mutex.release_all()?;
+        let res = block(py, &self.waiters, &self.signalled, deadline, |_| {
+            flag.load(SeqCst)
+        });
+        if !matches!(res, Ok(true)) {
+            let mut waiters = lock(&self.waiters);
+            match waiters.iter().position(|waiter| Arc::ptr_eq(waiter, &flag)) {
+                Some(i) => drop(waiters.remove(i)),
+                // Signalled after all, which must not be lost
+                None if res.is_ok() => {
+                    drop(waiters);
+                    mutex.restore(py, count);
+                    return Ok(true);
+                }
+                // Interrupted, so pass the signal on to the next waiter
+                None => {
+                    if let Some(next) = waiters.pop_front() {
+                        next.store(true, SeqCst);
+                        self.signalled.notify_all();
+                    }
+                }
+            }
+        }
+        mutex.restore(py, count);
+        res
+    }
+
+    /// Wake the thread that has been waiting the longest, if any.
+    pub fn signal(&self) -> PyResult<()> {
+        self.mutex.get().check_owned("signal")?;
+        if let Some(waiter) = lock(&self.waiters).pop_front() {
+            waiter.store(true, SeqCst);
+            self.signalled.notify_all();
+        }
+        Ok(())
+    }
+
+    /// Wake every waiting thread.
+    pub fn broadcast(&self) -> PyResult<()> {
+        self.mutex.get().check_owned("broadcast")?;
+        for waiter in lock(&self.waiters).drain(..) {
+            waiter.store(true, SeqCst);
+        }
+        self.signalled.notify_all();
+        Ok(())
+    }
+
+    fn __enter__(slf: PyRef<'_, Self>) -> PyResult<PyRef<'_, Self>> {
+        slf.acquire(slf.py())?;
+        Ok(slf)
+    }
+
+    fn __exit__(
+        &self,
+        _exc_type: &Bound<'_, PyAny>,
+        _exc: &Bound<'_, PyAny>,
+        _tb: &Bound<'_, PyAny>,
+    ) -> PyResult<()> {
+        self.release()
+    }
+}
+
+/// A counting semaphore.
+#[pyclass(module = "haxe_atomic", frozen)]
+#[derive(Debug, Default)]
+pub struct Semaphore {
+    permits: StdMutex<usize>,
+    released: Condvar,
+}
+
+#[pymethods]
+impl Semaphore {
+    #[new]
+    #[pyo3(signature = (value = 1))]
+    fn new(value: usize) -> Self {
+        Self {
+            permits: StdMutex::new(value),
+            released: Condvar::new(),
+        }
+    }
+
+    pub fn acquire(&self, py: Python<'_>) -> PyResult<()> {
+        self.acquire_until(py, None)?;
+        Ok(())
+    }
+
+    #[pyo3(signature = (timeout = 0.0))]
+    pub fn try_acquire(&self, py: Python<'_>, timeout: f64) -> PyResult<bool> {
+        self.acquire_until(py, wait::deadline(Some(timeout))?)
+    }
+
+    pub fn release(&self) {
+        *lock(&self.permits) += 1;
+        self.released.notify_one();
+    }
+
+    fn __enter__(slf: PyRef<'_, Self>) -> PyResult<PyRef<'_, Self>> {
+        slf.acquire(slf.py())?;
+        Ok(slf)
+    }
+
+    fn __exit__(
+        &self,
+        _exc_type: &Bound<'_, PyAny>,
+        _exc: &Bound<'_, PyAny>,
+        _tb: &Bound<'_, PyAny>,
+    ) {
+        self.release()
+    }
+}
+
+impl Semaphore {
+    fn acquire_until(&self, py: Python<'_>, deadline: Option<Instant>) -> PyResult<bool> {
+        block(py, &self.permits, &self.released, deadline, |permits| {
+            if *permits == 0 {
+                return false;
+            }
+            *permits -= 1;
+            true
+        })
+    }
+}
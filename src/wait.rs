@@ -0,0 +1,189 @@
+//! Blocking until an atomic is notified, using futexes on Linux and a table of
+//! condition variables everywhere else.
+
+use pyo3::{exceptions::PyValueError, prelude::*};
+use std::{
+    sync::atomic::{AtomicU32, Ordering::SeqCst},
+    time::{Duration, Instant},
+};
+
+// Blocked threads wake up this often to run python signal handlers
+const SIGNAL_CHECK_INTERVAL: Duration = Duration::from_millis(50);
+
+/// The point in time `timeout` seconds from now, if any. Timeouts too long to
+/// represent, like infinity, never expire.
+pub fn deadline(timeout: Option<f64>) -> PyResult<Option<Instant>> {
+    let Some(timeout) = timeout else {
+        return Ok(None);
+    };
+    match Duration::try_from_secs_f64(timeout) {
+        Ok(timeout) => Ok(Instant::now().checked_add(timeout)),
+        Err(_) if timeout > 0.0 => Ok(None),
+        Err(err) => Err(PyValueError::new_err(format!("invalid timeout: {err}"))),
+    }
+}
+
+/// How long to block before checking for signals again, or `None` once `deadline` has passed.
+pub fn next_slice(deadline: Option<Instant>) -> Option<Duration> {
+    match deadline {
+        Some(deadline) => match deadline.checked_duration_since(Instant::now()) {
+            Some(remaining) if !remaining.is_zero() => Some(remaining.min(SIGNAL_CHECK_INTERVAL)),
+            _ => None,
+        },
+        None => Some(SIGNAL_CHECK_INTERVAL),
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct Notifier {
+    // Incremented on every notification, this is the word waiters block on
+    epoch: AtomicU32,
+    waiters: AtomicU32,
+}
+
+impl Notifier {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Block while `unchanged` returns true, returning false if `timeout` seconds
+    /// elapse first.
+    pub fn wait(
+        &self,
+        py: Python<'_>,
+        timeout: Option<f64>,
+        unchanged: impl Fn() -> bool,
+    ) -> PyResult<bool> {
+        self.wait_until(py, deadline(timeout)?, unchanged)
+    }
+
+    /// Like `wait`, but giving up at `deadline`.
+    pub fn wait_until(
+        &self,
+        py: Python<'_>,
+        deadline: Option<Instant>,
+        unchanged: impl Fn() -> bool,
+    ) -> PyResult<bool> {
+        self.waiters.fetch_add(1, SeqCst);
+        let res = self.block(py, deadline, unchanged);
+        self.waiters.fetch_sub(1, SeqCst);
+        res
+    }
+
+    fn block(
+        &self,
+        py: Python<'_>,
+        deadline: Option<Instant>,
+        unchanged: impl Fn() -> bool,
+    ) -> PyResult<bool> {
+        loop {
+            // Loading the epoch before checking the value means a notification
+            // sent after the check makes the wait below return immediately
+            let epoch = self.epoch.load(SeqCst);
+            if !unchanged() {
+                return Ok(true);
+            }
+            let Some(slice) = next_slice(deadline) else {
+                return Ok(false);
+            };
+            py.allow_threads(|| imp::wait(&self.epoch, epoch, slice));
+            py.check_signals()?;
+        }
+    }
+
+    pub fn notify_one(&self) {
+        self.epoch.fetch_add(1, SeqCst);
+        if self.waiters.load(SeqCst) != 0 {
+            imp::wake(&self.epoch, false);
+        }
+    }
+
+    pub fn notify_all(&self) {
+        self.epoch.fetch_add(1, SeqCst);
+        if self.waiters.load(SeqCst) != 0 {
+            imp::wake(&self.epoch, true);
+        }
+    }
+}
+
+#[cfg(any(target_os = "linux", target_os = "android"))]
+mod imp {
+    use std::{sync::atomic::AtomicU32, time::Duration};
+
+    pub fn wait(word: &AtomicU32, expected: u32, timeout: Duration) {
+        let timeout = libc::timespec {
+            tv_sec: timeout.as_secs() as libc::time_t,
+            tv_nsec: timeout.subsec_nanos() as libc::c_long,
+        };
+        // Safety: `word` is a valid, aligned 32 bit integer. Spurious returns are
+        // handled by the caller, so the result does not matter
+        unsafe {
+            libc::syscall(
+                libc::SYS_futex,
+                word.as_ptr(),
+                libc::FUTEX_WAIT | libc::FUTEX_PRIVATE_FLAG,
+                expected,
+                &timeout as *const libc::timespec,
+            );
+        }
+    }
+
+    pub fn wake(word: &AtomicU32, all: bool) {
+        let count = if all { libc::c_int::MAX } else { 1 };
+        // Safety: `word` is a valid, aligned 32 bit integer
+        unsafe {
+            libc::syscall(
+                libc::SYS_futex,
+                word.as_ptr(),
+                libc::FUTEX_WAKE | libc::FUTEX_PRIVATE_FLAG,
+                count,
+            );
+        }
+    }
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "android")))]
+mod imp {
+    use std::{
+        sync::{
+            atomic::{AtomicU32, Ordering::SeqCst},
+            Condvar, Mutex, PoisonError,
+        },
+        time::Duration,
+    };
+
+    // Words are hashed into a fixed set of buckets, so unrelated waiters may
+    // share a condition variable and every wake has to notify all of them
+    const BUCKETS: usize = 64;
+
+    struct Bucket {
+        lock: Mutex<()>,
+        cond: Condvar,
+    }
+
+    static TABLE: [Bucket; BUCKETS] = [const {
+        Bucket {
+            lock: Mutex::new(()),
+            cond: Condvar::new(),
+        }
+    }; BUCKETS];
+
+    fn bucket(word: &AtomicU32) -> &'static Bucket {
+        &TABLE[(word as *const AtomicU32 as usize >> 2) % BUCKETS]
+    }
+
+    pub fn wait(word: &AtomicU32, expected: u32, timeout: Duration) {
+        let bucket = bucket(word);
+        let guard = bucket.lock.lock().unwrap_or_else(PoisonError::into_inner);
+        if word.load(SeqCst) == expected {
+            let _ = bucket.cond.wait_timeout(guard, timeout);
+        }
+    }
+
+    pub fn wake(word: &AtomicU32, _all: bool) {
+        let bucket = bucket(word);
+        // Taking the lock orders this wake after any waiter's check of `word`
+        drop(bucket.lock.lock().unwrap_or_else(PoisonError::into_inner));
+        bucket.cond.notify_all();
+    }
+}
@@ -0,0 +1,187 @@
+//! Waking asyncio tasks awaiting a change of an atomic.
+//!
+//! Every write to a watched atomic schedules the pending waiters on their event
+//! loops with `call_soon_threadsafe`, where they re-check their condition and
+//! either resolve their future or register themselves again. A waiter whose
+//! future is cancelled removes itself from the done callback of the future.
+//!
+//! A waiter registers before checking the value again, and a write checks for
+//! waiters after writing, so that one of the two always sees the other. Writes
+//! with a weaker ordering than `SeqCst` need a fence for this.
+
+use pyo3::{prelude::*, PyTraverseError, PyVisit};
+use std::sync::{
+    atomic::{self, fence, AtomicUsize, Ordering::SeqCst},
+    Arc, Mutex, PoisonError,
+};
+
+#[derive(Debug, Default)]
+pub struct Watchers {
+    // Number of registered waiters, so that writes can skip taking the lock
+    count: AtomicUsize,
+    waiters: Mutex<Vec<Py<ChangeWaiter>>>,
+}
+
+impl Watchers {
+    pub fn new() -> Arc<Self> {
+        Arc::default()
+    }
+
+    fn register(&self, waiter: Py<ChangeWaiter>) {
+        let mut waiters = self.waiters.lock().unwrap_or_else(PoisonError::into_inner);
+        waiters.push(waiter);
+        self.count.store(waiters.len(), SeqCst);
+    }
+
+    fn deregister(&self, waiter: &ChangeWaiter) {
+        let removed = {
+            let mut waiters = self.waiters.lock().unwrap_or_else(PoisonError::into_inner);
+            let (removed, kept) = std::mem::take(&mut *waiters)
+                .into_iter()
+                .partition(|registered| std::ptr::eq(registered.get(), waiter));
+            *waiters = kept;
+            self.count.store(waiters.len(), SeqCst);
+            removed
+        };
+        // Releasing may run arbitrary python code, so it must happen outside the lock
+        drop::<Vec<_>>(removed);
+    }
+
+    fn take(&self) -> Vec<Py<ChangeWaiter>> {
+        let mut waiters = self.waiters.lock().unwrap_or_else(PoisonError::into_inner);
+        self.count.store(0, SeqCst);
+        std::mem::take(&mut *waiters)
+    }
+
+    /// Schedule every pending waiter on its event loop after the atomic was
+    /// written to with `ordering`.
+    pub fn wake(&self, py: Python<'_>, ordering: atomic::Ordering) {
+        // `SeqCst` writes are already ordered before the load of `count`
+        if ordering != SeqCst {
+            fence(SeqCst);
+        }
+        if self.count.load(SeqCst) == 0 {
+            return;
+        }
+        for waiter in self.take() {
+            let event_loop = waiter.get().event_loop.clone_ref(py);
+            // The loop may have been closed in the meantime, its waiters can never be resumed
+            let _ = event_loop.call_method1(py, "call_soon_threadsafe", (waiter,));
+        }
+    }
+
+    pub fn traverse(&self, visit: &PyVisit<'_>) -> Result<(), PyTraverseError> {
+        // Not reporting references while another thread holds the lock only keeps
+        // them alive for longer
+        if let Ok(waiters) = self.waiters.try_lock() {
+            for waiter in waiters.iter() {
+                visit.call(waiter)?;
+            }
+        }
+        Ok(())
+    }
+
+    pub fn clear(&self) {
+        drop(self.take());
+    }
+}
+
+#[derive(Debug)]
+pub enum Condition {
+    Changed { expected: Py<PyAny>, identity: bool },
+    Until(Py<PyAny>),
+}
+
+impl Condition {
+    fn test(&self, value: &Bound<'_, PyAny>) -> PyResult<bool> {
+        match self {
+            Condition::Changed {
+                expected,
+                identity: true,
+            } => Ok(!value.is(expected)),
+            Condition::Changed { expected, .. } => value.ne(expected),
+            Condition::Until(predicate) => predicate.bind(value.py()).call1((value,))?.is_truthy(),
+        }
+    }
+}
+
+#[pyclass(module = "haxe_atomic", frozen)]
+pub struct ChangeWaiter {
+    atomic: Py<PyAny>,
+    watchers: Arc<Watchers>,
+    event_loop: Py<PyAny>,
+    future: Py<PyAny>,
+    condition: Condition,
+}
+
+#[pymethods]
+impl ChangeWaiter {
+    fn __call__(slf: &Bound<'_, Self>) -> PyResult<()> {
+        let this = slf.get();
+        let py = slf.py();
+        let future = this.future.bind(py);
+        let mut registered = false;
+        loop {
+            if future.call_method0("done")?.is_truthy()? {
+                return Ok(());
+            }
+            let value = this.atomic.bind(py).call_method0("load")?;
+            match this.condition.test(&value) {
+                Ok(true) => {
+                    future.call_method1("set_result", (value,))?;
+                    return Ok(());
+                }
+                Err(err) => {
+                    future.call_method1("set_exception", (err.value(py),))?;
+                    return Ok(());
+                }
+                // Check again after registering in case a write happened in between
+                Ok(false) if !registered => {
+                    this.watchers.register(slf.clone().unbind());
+                    registered = true;
+                }
+                Ok(false) => return Ok(()),
+            }
+        }
+    }
+
+    // Done callback of the future, which is only still registered if it was cancelled
+    fn deregister(&self, _future: &Bound<'_, PyAny>) {
+        self.watchers.deregister(self);
+    }
+
+    fn __traverse__(&self, visit: PyVisit<'_>) -> Result<(), PyTraverseError> {
+        visit.call(&self.atomic)?;
+        visit.call(&self.event_loop)?;
+        visit.call(&self.future)?;
+        match &self.condition {
+            Condition::Changed { expected, .. } => visit.call(expected),
+            Condition::Until(predicate) => visit.call(predicate),
+        }
+    }
+}
+
+/// Create a future on the running event loop that resolves to the value of
+/// `atomic` once it satisfies `condition`.
+pub fn wait<'py>(
+    atomic: &Bound<'py, PyAny>,
+    watchers: &Arc<Watchers>,
+    condition: Condition,
+) -> PyResult<Bound<'py, PyAny>> {
+    let py = atomic.py();
+    let event_loop = py.import("asyncio")?.getattr("get_running_loop")?.call0()?;
+    let future = event_loop.call_method0("create_future")?;
+    let waiter = Bound::new(
+        py,
+        ChangeWaiter {
+            atomic: atomic.clone().unbind(),
+            watchers: watchers.clone(),
+            event_loop: event_loop.unbind(),
+            future: future.clone().unbind(),
+            condition,
+        },
+    )?;
+    future.call_method1("add_done_callback", (waiter.getattr("deregister")?,))?;
+    ChangeWaiter::__call__(&waiter)?;
+    Ok(future)
+}
@@ -0,0 +1,62 @@
+import ctypes
+import sys
+import unittest
+
+from haxe_atomic import AtomicIntArray, AtomicObjectArray
+
+try:
+    memoryview(AtomicIntArray(0))
+    EXPORTS_BUFFERS = True
+except TypeError:
+    # Built for the limited API of python versions before 3.11
+    EXPORTS_BUFFERS = False
+
+
+class AtomicIntArrayTest(unittest.TestCase):
+    def test_keyword_arguments_match_the_stub(self):
+        arr = AtomicIntArray(4)
+        self.assertEqual(arr.store(1, value=2), 2)
+        self.assertEqual(arr.exchange(1, value=3), 2)
+        self.assertEqual(arr.compare_exchange(1, expected=3, desired=4), 3)
+        self.assertEqual(arr.compare_exchange_result(1, expected=4, desired=5), (True, 4))
+        self.assertFalse(arr.compare_exchange_weak(1, expected=4, desired=6)[0])
+        self.assertEqual(arr.snapshot(), [0, 5, 0, 0])
+
+
+class AtomicObjectArrayTest(unittest.TestCase):
+    def test_keyword_arguments_match_the_stub(self):
+        arr = AtomicObjectArray(2)
+        self.assertEqual(arr.store(1, value="a"), "a")
+        self.assertEqual(arr.exchange(1, value="b"), "a")
+        self.assertEqual(arr.compare_exchange(1, expected="b", desired="c"), "b")
+        self.assertEqual(arr.snapshot(), [None, "c"])
+
+
+@unittest.skipUnless(EXPORTS_BUFFERS, "built without buffer support")
+class BufferTest(unittest.TestCase):
+    def test_memoryview_reads_the_slots(self):
+        arr = AtomicIntArray(3, 7)
+        arr[1] = -1
+        with memoryview(arr) as view:
+            self.assertEqual(view.format, "i")
+            self.assertEqual(view.shape, (3,))
+            self.assertEqual(view.strides, (4,))
+            self.assertTrue(view.readonly)
+            self.assertEqual(view.tolist(), [7, -1, 7])
+            arr[2] = 9
+            self.assertEqual(view[2], 9)
+
+    def test_buffer_is_read_only(self):
+        arr = AtomicIntArray(1)
+        with memoryview(arr) as view:
+            with self.assertRaises(TypeError):
+                view[0] = 1
+        # Asks for a writable buffer
+        with self.assertRaises(TypeError):
+            (ctypes.c_int * 1).from_buffer(arr)
+        # Released views do not keep the array alive
+        self.assertEqual(sys.getrefcount(arr), 2)
+
+
+if __name__ == "__main__":
+    unittest.main()
@@ -0,0 +1,62 @@
+import unittest
+
+from haxe_atomic import AtomicBitSet
+
+from util import THREADS, run_threads
+
+
+class AtomicBitSetTest(unittest.TestCase):
+    def test_iterates_in_ascending_order(self):
+        bits = AtomicBitSet(200)
+        for bit in (199, 3, 64, 0, 127, 63, 128):
+            bits.set(bit)
+        self.assertEqual(list(bits), [0, 3, 63, 64, 127, 128, 199])
+        bits.clear(64)
+        self.assertFalse(bits.test_and_clear(64))
+        self.assertFalse(bits.test_and_set(65))
+        self.assertEqual(list(bits), [0, 3, 63, 65, 127, 128, 199])
+        self.assertEqual(list(AtomicBitSet(200)), [])
+
+    def test_count(self):
+        bits = AtomicBitSet(130)
+        self.assertEqual(bits.count(), 0)
+        for bit in range(0, 130, 3):
+            bits.set(bit)
+        self.assertEqual(bits.count(), len(range(0, 130, 3)))
+        bits.clear(0)
+        self.assertEqual(bits.count(), len(range(3, 130, 3)))
+
+    def test_bits_past_the_end_of_the_last_word_are_out_of_range(self):
+        bits = AtomicBitSet(70)
+        for bit in (70, 127):
+            with self.assertRaises(IndexError):
+                bits.set(bit)
+            with self.assertRaises(IndexError):
+                bits.test(bit)
+            self.assertNotIn(bit, bits)
+        # Filling the set leaves the rest of the last word alone
+        self.assertEqual([bits.find_first_clear_and_set() for _ in range(70)], list(range(70)))
+        self.assertIsNone(bits.find_first_clear_and_set())
+        self.assertEqual(bits.count(), 70)
+        self.assertEqual(list(bits), list(range(70)))
+
+    def test_concurrent_callers_claim_different_bits(self):
+        nbits = 1000
+        bits = AtomicBitSet(nbits)
+        claimed = [[] for _ in range(THREADS)]
+
+        def run(i):
+            while True:
+                bit = bits.find_first_clear_and_set()
+                if bit is None:
+                    return
+                claimed[i].append(bit)
+
+        run_threads(run)
+        claimed = [bit for items in claimed for bit in items]
+        self.assertEqual(sorted(claimed), list(range(nbits)))
+        self.assertEqual(bits.count(), nbits)
+
+
+if __name__ == "__main__":
+    unittest.main()
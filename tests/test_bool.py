@@ -0,0 +1,16 @@
+import unittest
+
+from haxe_atomic import AtomicBool
+
+
+class AtomicBoolTest(unittest.TestCase):
+    def test_keyword_arguments_match_the_stub(self):
+        atomic = AtomicBool(False)
+        self.assertFalse(atomic.compare_exchange(expected=False, desired=True))
+        self.assertEqual(atomic.compare_exchange_result(expected=True, desired=False), (True, True))
+        self.assertFalse(atomic.compare_exchange_weak(expected=True, desired=True)[0])
+        self.assertFalse(atomic.load())
+
+
+if __name__ == "__main__":
+    unittest.main()
@@ -0,0 +1,45 @@
+import copy
+import pickle
+import sys
+import unittest
+
+from haxe_atomic import AtomicBool, AtomicFloat, AtomicInt, AtomicUInt64
+
+
+class FromBufferTest(unittest.TestCase):
+    def test_writes_are_shared(self):
+        buf = bytearray(16)
+        atomic = AtomicInt.from_buffer(buf, 4)
+        atomic.store(-2)
+        self.assertEqual(buf[4:8], (-2).to_bytes(4, sys.byteorder, signed=True))
+        self.assertEqual(AtomicInt.from_buffer(buf, 4).load(), -2)
+
+    def test_nonzero_bytes_are_true(self):
+        buf = bytearray(1)
+        atomic = AtomicBool.from_buffer(buf)
+        buf[0] = 7
+        self.assertTrue(atomic.load())
+        self.assertTrue(atomic.fetch_xor(True))
+        self.assertEqual(buf[0], 0)
+
+    def test_cannot_be_copied_or_pickled(self):
+        buf = bytearray(16)
+        for atomic in (
+            AtomicBool.from_buffer(buf),
+            AtomicInt.from_buffer(buf, 4),
+            AtomicUInt64.from_buffer(buf, 8),
+            AtomicFloat.from_buffer(buf, 8),
+        ):
+            with self.subTest(type(atomic).__name__):
+                for operation in (copy.copy, copy.deepcopy, pickle.dumps):
+                    with self.assertRaises(TypeError):
+                        operation(atomic)
+
+    def test_inline_atomics_are_copied(self):
+        atomic = AtomicInt(5)
+        self.assertEqual(pickle.loads(pickle.dumps(atomic)).load(), 5)
+        self.assertEqual(copy.copy(atomic).load(), 5)
+
+
+if __name__ == "__main__":
+    unittest.main()
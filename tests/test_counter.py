@@ -0,0 +1,74 @@
+import threading
+import unittest
+
+from haxe_atomic import StripedCounter
+
+from util import THREADS, run_threads
+
+ITERATIONS = 20000
+
+
+class StripedCounterTest(unittest.TestCase):
+    def test_counts_every_increment(self):
+        # More threads than cells, as many, and the default
+        for cells in (2, THREADS, None):
+            counter = StripedCounter(cells)
+
+            def run(i):
+                for _ in range(ITERATIONS):
+                    counter.increment()
+                counter.add(i)
+
+            run_threads(run)
+            self.assertEqual(counter.load(), THREADS * ITERATIONS + sum(range(THREADS)))
+            self.assertEqual(int(counter), counter.load())
+
+    def test_sum_then_reset_loses_no_additions(self):
+        counter = StripedCounter(4)
+        adding = threading.Barrier(THREADS)
+        sums = []
+
+        def run(i):
+            if i == 0:
+                while adding.n_waiting < THREADS - 1:
+                    sums.append(counter.sum_then_reset())
+                adding.wait()
+                return
+            for _ in range(ITERATIONS):
+                counter.add(3)
+            adding.wait()
+
+        run_threads(run)
+        self.assertEqual(sum(sums) + counter.sum_then_reset(), 3 * (THREADS - 1) * ITERATIONS)
+        self.assertEqual(counter.load(), 0)
+
+    def test_reset(self):
+        counter = StripedCounter()
+        counter.add(5)
+        counter.reset()
+        self.assertEqual(counter.load(), 0)
+        self.assertEqual(counter.sum_then_reset(), 0)
+
+    def test_add_wraps_around(self):
+        counter = StripedCounter(1)
+        counter.add(2**63 - 1)
+        counter.add(1)
+        self.assertEqual(counter.load(), -(2**63))
+        counter.add(2**64 + 5)
+        self.assertEqual(counter.load(), -(2**63) + 5)
+        counter.add(-6)
+        self.assertEqual(counter.sum_then_reset(), 2**63 - 1)
+
+    def test_sum_wraps_around_across_cells(self):
+        counter = StripedCounter(2)
+        # New threads are given consecutive cells
+        run_threads(lambda i: counter.add(2**62), count=2)
+        self.assertEqual(counter.load(), -(2**63))
+
+    def test_cells_must_be_positive(self):
+        with self.assertRaises(ValueError):
+            StripedCounter(0)
+
+
+if __name__ == "__main__":
+    unittest.main()
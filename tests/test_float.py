@@ -0,0 +1,24 @@
+import math
+import unittest
+
+from haxe_atomic import AtomicFloat
+
+
+class AtomicFloatTest(unittest.TestCase):
+    def test_keyword_arguments_match_the_stub(self):
+        atomic = AtomicFloat(1.0)
+        self.assertEqual(atomic.store(value=2.0), 2.0)
+        self.assertEqual(atomic.exchange(value=3.0), 2.0)
+        self.assertEqual(atomic.compare_exchange(expected=3.0, desired=4.0), 3.0)
+        self.assertEqual(atomic.compare_exchange_result(expected=4.0, desired=5.0), (True, 4.0))
+        self.assertFalse(atomic.compare_exchange_weak(expected=4.0, desired=6.0)[0])
+        self.assertEqual(atomic.load(), 5.0)
+
+    def test_compare_exchange_is_bitwise(self):
+        atomic = AtomicFloat(math.nan)
+        self.assertTrue(atomic.compare_exchange_result(math.nan, 0.0)[0])
+        self.assertFalse(atomic.compare_exchange_result(-0.0, 1.0)[0])
+
+
+if __name__ == "__main__":
+    unittest.main()
@@ -0,0 +1,77 @@
+import unittest
+
+from haxe_atomic import AtomicInt, AtomicInt64, AtomicIntArray, AtomicUInt32, AtomicUInt64, StripedCounter
+
+INT_TYPES = (AtomicInt, AtomicInt64, AtomicUInt32, AtomicUInt64)
+
+
+class NumericTest(unittest.TestCase):
+    def test_compares_by_value(self):
+        for cls in INT_TYPES:
+            atomic = cls(1)
+            self.assertEqual(atomic, 1)
+            self.assertEqual(atomic, cls(1))
+            self.assertLess(atomic, 2)
+            atomic += 1
+            self.assertEqual(atomic, 2)
+
+    def test_keyword_arguments_match_the_stub(self):
+        for cls in INT_TYPES:
+            atomic = cls(1)
+            self.assertEqual(atomic.compare_exchange(expected=1, desired=2), 1)
+            self.assertEqual(atomic.compare_exchange_result(expected=2, desired=3), (True, 2))
+            self.assertFalse(atomic.compare_exchange_weak(expected=2, desired=4)[0])
+            self.assertEqual(atomic.load(), 3)
+
+    def test_is_unhashable(self):
+        for cls in INT_TYPES:
+            with self.assertRaises(TypeError):
+                hash(cls(1))
+            with self.assertRaises(TypeError):
+                cls(1) in {1}
+
+
+class OverflowTest(unittest.TestCase):
+    def test_atomic_int_wraps_by_default(self):
+        atomic = AtomicInt(2**31 - 1)
+        self.assertEqual(atomic.fetch_add(1), 2**31 - 1)
+        self.assertEqual(atomic.load(), -(2**31))
+        self.assertEqual(AtomicInt(2**32 + 5).load(), 5)
+        self.assertEqual(repr(AtomicInt(1)), "AtomicInt(1)")
+
+    def test_wider_types_are_strict_by_default(self):
+        for cls in (AtomicInt64, AtomicUInt32, AtomicUInt64):
+            with self.assertRaises(OverflowError):
+                cls(-1 if cls is not AtomicInt64 else 2**63)
+            with self.assertRaises(OverflowError):
+                cls(1).store(2**64)
+            self.assertEqual(repr(cls(1)), "{}(1)".format(cls.__name__))
+
+    def test_wrapping_rejects_floats(self):
+        atomic = AtomicInt(1)
+        arr = AtomicIntArray(1, 1)
+        counter = StripedCounter()
+        for write in (
+            atomic.store,
+            atomic.fetch_add,
+            lambda val: arr.store(0, val),
+            lambda val: arr.fetch_add(0, val),
+            counter.add,
+        ):
+            with self.assertRaises(TypeError):
+                write(2.5)
+        self.assertEqual(atomic.load(), 1)
+        self.assertEqual(arr.snapshot(), [1])
+        self.assertEqual(counter.load(), 0)
+
+    def test_strict_mode_can_be_changed(self):
+        with self.assertRaises(OverflowError):
+            AtomicInt(1, strict=True).store(2**31)
+        atomic = AtomicUInt32(0, strict=False)
+        atomic.store(-1)
+        self.assertEqual(atomic.load(), 2**32 - 1)
+        self.assertEqual(repr(atomic), "AtomicUInt32(4294967295, strict=False)")
+
+
+if __name__ == "__main__":
+    unittest.main()
@@ -0,0 +1,94 @@
+import gc
+import threading
+import unittest
+
+from haxe_atomic import ConcurrentMap
+
+from util import THREADS, Tracked, run_threads
+
+
+class ComputeIfAbsentTest(unittest.TestCase):
+    def test_func_is_called_once_per_key(self):
+        m = ConcurrentMap()
+        calls = []
+
+        def run(i):
+            for key in range(1000):
+                m.compute_if_absent(key, lambda key: calls.append(key) or key * 2)
+
+        run_threads(run)
+        self.assertEqual(sorted(calls), list(range(1000)))
+        self.assertEqual(len(m), 1000)
+        self.assertEqual(m.get(10), 20)
+
+    def test_func_may_read_the_map(self):
+        m = ConcurrentMap()
+        m.insert("a", 1)
+        self.assertEqual(m.compute_if_absent("b", lambda key: m.get("a") + 1), 2)
+
+    def test_func_cannot_modify_the_map(self):
+        m = ConcurrentMap(stripes=4)
+        for other in range(8):
+            with self.assertRaises(RuntimeError):
+                m.compute_if_absent(100, lambda key: m.insert(other, key))
+        self.assertEqual(len(m), 0)
+        # The stripes are released after the error
+        m.insert(100, 1)
+        self.assertEqual(m.compute_if_absent(100, lambda key: 2), 1)
+
+    def test_func_may_modify_another_map(self):
+        m, other = ConcurrentMap(), ConcurrentMap()
+        self.assertEqual(m.compute_if_absent(1, lambda key: other.insert(key, 2) or 3), 3)
+        self.assertEqual(other.get(1), 2)
+
+    def test_reentering_threads_do_not_deadlock(self):
+        # Each thread holds the stripe of its own key and then tries to insert the
+        # key of the other thread
+        m = ConcurrentMap(stripes=2)
+        barrier = threading.Barrier(2)
+        errors = []
+
+        def func(key):
+            barrier.wait(5)
+            try:
+                m.insert(1 - key, key)
+            except RuntimeError as err:
+                errors.append(err)
+            return key
+
+        run_threads(lambda i: m.compute_if_absent(i, func), count=2)
+        self.assertEqual(len(errors), 2)
+        self.assertEqual(sorted(m), [0, 1])
+
+
+class IterTest(unittest.TestCase):
+    def test_iterates_over_every_entry(self):
+        m = ConcurrentMap()
+        for key in range(100):
+            m.insert(key, -key)
+        self.assertEqual(sorted(m), list(range(100)))
+        self.assertEqual(sorted(m.items()), [(key, -key) for key in range(100)])
+
+    def test_iterator_stored_in_map_is_collected(self):
+        m = ConcurrentMap()
+        m.insert("tracked", Tracked())
+        m.insert("iter", iter(m))
+        del m
+        gc.collect()
+        self.assertEqual(Tracked.live(), 0)
+
+    def test_concurrent_inserts_and_removes(self):
+        m = ConcurrentMap()
+
+        def run(i):
+            for key in range(i, 4000, THREADS):
+                m.insert(key, i)
+            for key in range(i, 4000, 2 * THREADS):
+                self.assertEqual(m.remove(key), i)
+
+        run_threads(run)
+        self.assertEqual(len(m), 2000)
+
+
+if __name__ == "__main__":
+    unittest.main()
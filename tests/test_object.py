@@ -0,0 +1,137 @@
+import sys
+import threading
+import time
+import unittest
+
+from haxe_atomic import AtomicObject
+
+from util import THREADS, Tracked, run_threads
+
+ITERATIONS = 20000
+
+
+class LoadStoreTest(unittest.TestCase):
+    def tearDown(self):
+        self.assertEqual(Tracked.live(), 0)
+
+    def test_loaded_objects_are_alive(self):
+        atomic = AtomicObject(Tracked())
+
+        def run(i):
+            for _ in range(ITERATIONS):
+                if i % 2:
+                    atomic.load().check()
+                elif i % 4:
+                    atomic.store(Tracked())
+                else:
+                    atomic.exchange(Tracked()).check()
+
+        run_threads(run)
+        atomic.store(None)
+
+    def test_compare_exchange_does_not_lose_updates(self):
+        for identity in (False, True):
+            atomic = AtomicObject(0, identity=identity)
+
+            def run(i):
+                for _ in range(ITERATIONS // 10):
+                    while True:
+                        current = atomic.load()
+                        if atomic.compare_exchange_result(current, current + 1)[0]:
+                            break
+
+            run_threads(run)
+            self.assertEqual(atomic.load(), THREADS * (ITERATIONS // 10))
+
+    def test_compare_exchange_releases_objects(self):
+        atomic = AtomicObject(Tracked())
+
+        def run(i):
+            for _ in range(ITERATIONS):
+                current = atomic.load()
+                current.check()
+                ok, previous = atomic.compare_exchange_result(current, Tracked())
+                previous.check()
+                self.assertEqual(ok, previous is current)
+
+        run_threads(run)
+        atomic.store(None)
+
+    def test_failed_exchange_returns_the_object_it_failed_on(self):
+        x, y = object(), object()
+        atomic = AtomicObject(x)
+        stop = threading.Event()
+        # Objects swapped in by the main thread, as seen by the flipping one
+        taken = []
+
+        def flip():
+            while not stop.is_set():
+                for value in (y, x):
+                    previous = atomic.exchange(value)
+                    if previous is not x and previous is not y:
+                        taken.append(previous)
+
+        flipper = threading.Thread(target=flip)
+        flipper.start()
+        swapped = 0
+        try:
+            for _ in range(ITERATIONS):
+                # Returning `x` must mean that the exchange succeeded, even if the
+                # flipper puts `x` back right after a failed attempt
+                if atomic.compare_exchange_identity(x, object()) is x:
+                    swapped += 1
+        finally:
+            stop.set()
+            flipper.join()
+        final = atomic.load()
+        self.assertEqual(swapped, len(taken) + (final is not x and final is not y))
+
+    def test_refcounts_are_balanced(self):
+        shared = object()
+        before = sys.getrefcount(shared)
+        atomic = AtomicObject(shared)
+
+        def run(i):
+            for _ in range(ITERATIONS):
+                atomic.store(shared)
+                atomic.exchange(shared)
+                atomic.compare_exchange(shared, shared)
+                atomic.compare_exchange_identity(shared, shared)
+                atomic.load()
+
+        run_threads(run)
+        del atomic
+        self.assertEqual(sys.getrefcount(shared), before)
+
+
+class ReclamationTest(unittest.TestCase):
+    def test_replaced_objects_are_released_during_concurrent_loads(self):
+        atomic = AtomicObject(Tracked())
+        stop = threading.Event()
+
+        def load():
+            while not stop.is_set():
+                atomic.load().check()
+
+        readers = [threading.Thread(target=load) for _ in range(4)]
+        for reader in readers:
+            reader.start()
+        try:
+            for _ in range(ITERATIONS):
+                atomic.store(Tracked())
+            # Readers never stop loading in between, so only a bounded number of
+            # replaced objects may still be waiting for them
+            deadline = time.monotonic() + 5
+            while Tracked.live() > ITERATIONS // 2 and time.monotonic() < deadline:
+                atomic.store(Tracked())
+            self.assertLess(Tracked.live(), ITERATIONS // 2)
+        finally:
+            stop.set()
+            for reader in readers:
+                reader.join()
+        atomic.store(None)
+        self.assertEqual(Tracked.live(), 0)
+
+
+if __name__ == "__main__":
+    unittest.main()
@@ -0,0 +1,145 @@
+import gc
+import threading
+import time
+import unittest
+
+from haxe_atomic import ConcurrentQueue, QueueClosed
+
+from util import THREADS, Tracked, run_threads
+
+ITEMS = 5000
+# Unbounded and bounded
+CAPACITIES = (None, 16)
+
+
+class QueueTest(unittest.TestCase):
+    def tearDown(self):
+        gc.collect()
+        self.assertEqual(Tracked.live(), 0)
+
+    def test_fifo(self):
+        for capacity in CAPACITIES:
+            q = ConcurrentQueue(capacity)
+            for i in range(10):
+                self.assertTrue(q.try_push(i))
+            self.assertEqual(len(q), 10)
+            self.assertEqual([q.pop() for _ in range(10)], list(range(10)))
+            self.assertIsNone(q.try_pop())
+            self.assertEqual(q.try_pop("empty"), "empty")
+
+    def test_bounded_queue_refuses_items_when_full(self):
+        for capacity in (1, 2, 3):
+            q = ConcurrentQueue(capacity)
+            self.assertEqual(q.capacity, capacity)
+            for _ in range(capacity):
+                self.assertTrue(q.try_push(Tracked()))
+            self.assertFalse(q.try_push(Tracked()))
+            self.assertFalse(q.push(Tracked(), timeout=0.01))
+            self.assertEqual(len(q), capacity)
+            # Freeing a cell makes room for exactly one more item
+            q.pop()
+            self.assertTrue(q.try_push(Tracked()))
+            self.assertFalse(q.try_push(Tracked()))
+
+    def test_every_item_is_popped_once(self):
+        producers = THREADS // 2
+        for capacity in CAPACITIES:
+            q = ConcurrentQueue(capacity)
+            popped = [[] for _ in range(THREADS)]
+            done = threading.Barrier(producers)
+
+            def run(i):
+                if i < producers:
+                    for n in range(ITEMS):
+                        q.push((i, n))
+                    # The last producer to finish lets the consumers stop
+                    if done.wait() == 0:
+                        q.close()
+                    return
+                while True:
+                    try:
+                        popped[i].append(q.pop())
+                    except QueueClosed:
+                        return
+
+            run_threads(run)
+            items = [item for items in popped for item in items]
+            self.assertEqual(len(items), producers * ITEMS)
+            self.assertEqual(set(items), {(i, n) for i in range(producers) for n in range(ITEMS)})
+            # Items of one producer are popped in the order they were pushed
+            for items in popped:
+                for i in range(producers):
+                    ns = [n for producer, n in items if producer == i]
+                    self.assertEqual(ns, sorted(ns))
+
+
+class CloseTest(unittest.TestCase):
+    def tearDown(self):
+        gc.collect()
+        self.assertEqual(Tracked.live(), 0)
+
+    def test_items_can_be_popped_after_close(self):
+        for capacity in CAPACITIES:
+            q = ConcurrentQueue(capacity)
+            q.push(1)
+            q.close()
+            self.assertTrue(q.closed)
+            with self.assertRaises(QueueClosed):
+                q.push(Tracked())
+            with self.assertRaises(QueueClosed):
+                q.try_push(Tracked())
+            self.assertEqual(q.pop(), 1)
+            with self.assertRaises(QueueClosed):
+                q.pop()
+
+    def test_close_wakes_waiting_pop(self):
+        q = ConcurrentQueue()
+        threading.Timer(0.05, q.close).start()
+        with self.assertRaises(QueueClosed):
+            q.pop(timeout=5)
+
+    def test_close_wakes_waiting_push(self):
+        q = ConcurrentQueue(capacity=1)
+        q.push(Tracked())
+        threading.Timer(0.05, q.close).start()
+        with self.assertRaises(QueueClosed):
+            q.push(Tracked(), timeout=5)
+
+    def test_pop_times_out(self):
+        for capacity in CAPACITIES:
+            q = ConcurrentQueue(capacity)
+            start = time.monotonic()
+            with self.assertRaises(TimeoutError):
+                q.pop(timeout=0.05)
+            self.assertGreaterEqual(time.monotonic() - start, 0.04)
+
+    def test_push_waits_for_room(self):
+        q = ConcurrentQueue(capacity=1)
+        q.push(1)
+        threading.Timer(0.05, q.pop).start()
+        self.assertTrue(q.push(2, timeout=5))
+        self.assertEqual(q.pop(), 2)
+
+
+class ReleaseTest(unittest.TestCase):
+    def test_dropping_a_non_empty_queue_releases_items(self):
+        for capacity in CAPACITIES:
+            q = ConcurrentQueue(capacity)
+            for _ in range(10):
+                q.push(Tracked())
+            self.assertEqual(Tracked.live(), 10)
+            del q
+            self.assertEqual(Tracked.live(), 0)
+
+    def test_queued_cycles_are_collected(self):
+        for capacity in CAPACITIES:
+            q = ConcurrentQueue(capacity)
+            q.push(Tracked())
+            q.push(q)
+            del q
+            gc.collect()
+            self.assertEqual(Tracked.live(), 0)
+
+
+if __name__ == "__main__":
+    unittest.main()
@@ -0,0 +1,128 @@
+import gc
+import sys
+import threading
+import time
+import unittest
+
+from haxe_atomic import SpscByteRing, SpscRing
+
+from util import Tracked
+
+ITEMS = 5000
+# Only free-threaded builds run two producers at the same time
+PARALLEL = not getattr(sys, "_is_gil_enabled", lambda: True)()
+
+
+class SpscRingTest(unittest.TestCase):
+    def tearDown(self):
+        gc.collect()
+        self.assertEqual(Tracked.live(), 0)
+
+    def test_fifo_across_wraparound(self):
+        ring = SpscRing(3)
+        expected = []
+        popped = []
+        n = 0
+        for batch in (1, 2, 3, 2, 1, 3, 3, 2):
+            pushed = ring.push_many(list(range(n, n + batch)))
+            expected.extend(range(n, n + pushed))
+            n += batch
+            popped.extend(ring.pop_many(2))
+        popped.extend(ring.pop_many())
+        self.assertEqual(popped, expected)
+        self.assertEqual(len(ring), 0)
+
+    def test_push_many_appends_what_fits(self):
+        ring = SpscRing(3)
+        self.assertTrue(ring.push(0))
+        self.assertEqual(ring.push_many([1, 2, 3]), 2)
+        self.assertFalse(ring.push(3))
+        self.assertEqual(len(ring), 3)
+        self.assertEqual(ring.pop_many(None), [0, 1, 2])
+        self.assertEqual(ring.pop_many(), [])
+        self.assertEqual(ring.pop("empty"), "empty")
+
+    def test_items_are_released(self):
+        ring = SpscRing(4)
+        self.assertEqual(ring.push_many([Tracked() for _ in range(6)]), 4)
+        self.assertEqual(Tracked.live(), 4)
+        for item in ring.pop_many(2):
+            item.check()
+        del item
+        self.assertEqual(Tracked.live(), 2)
+        del ring
+        self.assertEqual(Tracked.live(), 0)
+
+    def test_consumer_sees_items_in_order(self):
+        ring = SpscRing(16)
+
+        def produce():
+            for n in range(ITEMS):
+                while not ring.push(n):
+                    time.sleep(0)
+
+        producer = threading.Thread(target=produce)
+        producer.start()
+        popped = []
+        while len(popped) < ITEMS:
+            popped.extend(ring.pop_many())
+        producer.join()
+        self.assertEqual(popped, list(range(ITEMS)))
+
+
+class SpscByteRingTest(unittest.TestCase):
+    def test_fifo_across_wraparound(self):
+        ring = SpscByteRing(3, 2)
+        self.assertEqual(ring.push_many(b"a0b0"), 2)
+        self.assertEqual(ring.pop(), b"a0")
+        # The second and third records wrap around the end of the buffer
+        self.assertEqual(ring.push_many(b"c0d0e0"), 2)
+        self.assertEqual(ring.pop_many(1), b"b0")
+        self.assertTrue(ring.push(b"f0"))
+        self.assertEqual(ring.pop_many(None), b"c0d0f0")
+        self.assertIsNone(ring.pop())
+        self.assertEqual(ring.pop_many(), b"")
+
+    def test_record_size_is_checked(self):
+        ring = SpscByteRing(4, 2)
+        with self.assertRaises(ValueError):
+            ring.push(b"abc")
+        with self.assertRaises(ValueError):
+            ring.push(b"abcd")
+        with self.assertRaises(ValueError):
+            ring.push_many(b"abc")
+        self.assertEqual(len(ring), 0)
+        self.assertEqual(ring.push_many(bytearray(b"abcd")), 2)
+        self.assertEqual(ring.pop_many(), b"abcd")
+
+
+@unittest.skipUnless(PARALLEL, "threads do not run in parallel")
+class ConcurrentUseTest(unittest.TestCase):
+    def test_second_producer_raises(self):
+        for ring, item in ((SpscRing(1024), None), (SpscByteRing(1024, 8), bytes(8))):
+            errors = []
+            stop = threading.Event()
+
+            def produce():
+                while not stop.is_set():
+                    try:
+                        ring.push(item)
+                    except RuntimeError as err:
+                        errors.append(err)
+                        stop.set()
+
+            producers = [threading.Thread(target=produce) for _ in range(2)]
+            for producer in producers:
+                producer.start()
+            deadline = time.monotonic() + 5
+            while not stop.is_set() and time.monotonic() < deadline:
+                ring.pop_many()
+            stop.set()
+            for producer in producers:
+                producer.join()
+            self.assertTrue(errors)
+            self.assertIn("already pushing to", str(errors[0]))
+
+
+if __name__ == "__main__":
+    unittest.main()
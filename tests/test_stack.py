@@ -0,0 +1,105 @@
+import gc
+import threading
+import unittest
+
+from haxe_atomic import AtomicStack
+
+from util import THREADS, Tracked, run_threads
+
+ITEMS = 5000
+
+
+class StackTest(unittest.TestCase):
+    def tearDown(self):
+        gc.collect()
+        self.assertEqual(Tracked.live(), 0)
+
+    def test_lifo(self):
+        s = AtomicStack()
+        self.assertTrue(s.is_empty())
+        for i in range(5):
+            s.push(i)
+        self.assertFalse(s.is_empty())
+        self.assertEqual(s.peek(), 4)
+        self.assertEqual([s.pop() for _ in range(5)], [4, 3, 2, 1, 0])
+        self.assertIsNone(s.pop())
+        self.assertEqual(s.pop("empty"), "empty")
+        self.assertEqual(s.peek("empty"), "empty")
+
+    def test_drain_returns_items_from_top_to_bottom(self):
+        s = AtomicStack()
+        for i in range(5):
+            s.push(i)
+        self.assertEqual(s.drain(), [4, 3, 2, 1, 0])
+        self.assertTrue(s.is_empty())
+        self.assertEqual(s.drain(), [])
+
+    def test_every_item_is_popped_once(self):
+        s = AtomicStack()
+        popped = [[] for _ in range(THREADS)]
+
+        def run(i):
+            for n in range(ITEMS):
+                s.push((i, n))
+                if n % 2:
+                    # Empty if the drain below took every item in between
+                    item = s.pop()
+                    if item is not None:
+                        popped[i].append(item)
+            if i == 0:
+                popped[i].extend(s.drain())
+
+        run_threads(run)
+        popped[0].extend(s.drain())
+        items = [item for items in popped for item in items]
+        self.assertEqual(len(items), THREADS * ITEMS)
+        self.assertEqual(set(items), {(i, n) for i in range(THREADS) for n in range(ITEMS)})
+
+    def test_peeked_items_are_alive(self):
+        s = AtomicStack()
+        s.push(Tracked())
+        stop = threading.Event()
+
+        def peek():
+            while not stop.is_set():
+                item = s.peek()
+                if item is not None:
+                    item.check()
+
+        readers = [threading.Thread(target=peek) for _ in range(4)]
+        for reader in readers:
+            reader.start()
+        try:
+            for _ in range(ITEMS):
+                s.push(Tracked())
+                s.pop().check()
+                s.pop().check()
+                s.push(Tracked())
+        finally:
+            stop.set()
+            for reader in readers:
+                reader.join()
+        s.drain()
+
+
+class ReleaseTest(unittest.TestCase):
+    def test_dropping_a_non_empty_stack_releases_items(self):
+        s = AtomicStack()
+        for _ in range(10):
+            s.push(Tracked())
+        s.pop()
+        self.assertEqual(Tracked.live(), 9)
+        del s
+        self.assertEqual(Tracked.live(), 0)
+
+    def test_stacked_cycles_are_collected(self):
+        s = AtomicStack()
+        s.push(Tracked())
+        s.push(s)
+        del s
+        gc.collect()
+        self.assertEqual(Tracked.live(), 0)
+
+
+if __name__ == "__main__":
+    unittest.main()
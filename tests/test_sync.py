@@ -0,0 +1,162 @@
+import threading
+import time
+import unittest
+
+from haxe_atomic import Condition, Mutex, RwLock, RwLockGuard, Semaphore
+
+
+def try_acquire_elsewhere(lock):
+    """Whether another thread can acquire `lock` right now, releasing it again."""
+    acquired = []
+
+    def run():
+        acquired.append(lock.try_acquire())
+        if acquired[0]:
+            lock.release()
+
+    thread = threading.Thread(target=run)
+    thread.start()
+    thread.join()
+    return acquired[0]
+
+
+class MutexTest(unittest.TestCase):
+    def test_is_recursive(self):
+        mutex = Mutex()
+        mutex.acquire()
+        self.assertTrue(mutex.try_acquire())
+        mutex.release()
+        self.assertFalse(try_acquire_elsewhere(mutex))
+        mutex.release()
+        self.assertTrue(try_acquire_elsewhere(mutex))
+
+    def test_release_by_non_owner_raises(self):
+        mutex = Mutex()
+        with self.assertRaises(RuntimeError):
+            mutex.release()
+        mutex.acquire()
+        errors = []
+
+        def release():
+            try:
+                mutex.release()
+            except RuntimeError as err:
+                errors.append(err)
+
+        thread = threading.Thread(target=release)
+        thread.start()
+        thread.join()
+        self.assertEqual(len(errors), 1)
+        mutex.release()
+
+
+class ConditionTest(unittest.TestCase):
+    def start_waiters(self, cond, count):
+        """Start `count` threads waiting on `cond`, returning them and their results."""
+        ready = []
+        results = []
+
+        def wait():
+            with cond:
+                ready.append(None)
+                results.append(cond.wait(timeout=5))
+
+        threads = [threading.Thread(target=wait) for _ in range(count)]
+        for thread in threads:
+            thread.start()
+        # Waiters only release the mutex by waiting
+        while True:
+            with cond:
+                if len(ready) == count:
+                    break
+            time.sleep(0.001)
+        return threads, results
+
+    def test_wait_times_out(self):
+        cond = Condition()
+        with cond:
+            start = time.monotonic()
+            self.assertFalse(cond.wait(timeout=0.05))
+            self.assertGreaterEqual(time.monotonic() - start, 0.04)
+
+    def test_signal_wakes_one_waiter(self):
+        cond = Condition()
+        threads, results = self.start_waiters(cond, 3)
+        with cond:
+            cond.signal()
+        time.sleep(0.2)
+        self.assertEqual(results, [True])
+        with cond:
+            cond.broadcast()
+        for thread in threads:
+            thread.join()
+        self.assertEqual(results, [True] * 3)
+
+    def test_broadcast_wakes_every_waiter(self):
+        cond = Condition()
+        threads, results = self.start_waiters(cond, 3)
+        with cond:
+            cond.broadcast()
+        for thread in threads:
+            thread.join()
+        self.assertEqual(results, [True] * 3)
+
+    def test_wait_restores_recursive_acquisitions(self):
+        mutex = Mutex()
+        cond = Condition(mutex)
+        mutex.acquire()
+        mutex.acquire()
+        acquired = []
+        # Another thread can take the mutex while this one waits
+        threading.Timer(0.05, lambda: acquired.append(try_acquire_elsewhere(mutex))).start()
+        self.assertFalse(cond.wait(timeout=0.2))
+        self.assertEqual(acquired, [True])
+        self.assertFalse(try_acquire_elsewhere(mutex))
+        mutex.release()
+        self.assertFalse(try_acquire_elsewhere(mutex))
+        mutex.release()
+        self.assertTrue(try_acquire_elsewhere(mutex))
+
+    def test_signal_requires_the_mutex(self):
+        cond = Condition()
+        with self.assertRaises(RuntimeError):
+            cond.signal()
+        with self.assertRaises(RuntimeError):
+            cond.wait(timeout=0)
+
+
+class SemaphoreTest(unittest.TestCase):
+    def test_try_acquire(self):
+        sem = Semaphore(1)
+        self.assertTrue(sem.try_acquire(timeout=0.05))
+        start = time.monotonic()
+        self.assertFalse(sem.try_acquire(timeout=0.05))
+        self.assertGreaterEqual(time.monotonic() - start, 0.04)
+        threading.Timer(0.05, sem.release).start()
+        self.assertTrue(sem.try_acquire(timeout=5))
+
+
+class RwLockTest(unittest.TestCase):
+    def test_guards_are_exported(self):
+        lock = RwLock()
+        self.assertIsInstance(lock.read(), RwLockGuard)
+        self.assertIsInstance(lock.write(), RwLockGuard)
+
+    def test_guards_hold_the_lock(self):
+        lock = RwLock()
+        with lock.read():
+            self.assertTrue(lock.try_acquire_read())
+            lock.release_read()
+            self.assertFalse(lock.try_acquire_write())
+        with lock.write():
+            acquired = []
+            reader = threading.Thread(target=lambda: acquired.append(lock.try_acquire_read()))
+            reader.start()
+            reader.join()
+            self.assertEqual(acquired, [False])
+        self.assertTrue(lock.try_acquire_write())
+        lock.release_write()
+
+
+if __name__ == "__main__":
+    unittest.main()
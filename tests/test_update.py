@@ -0,0 +1,78 @@
+import unittest
+
+from haxe_atomic import ABORT, AtomicBool, AtomicFloat, AtomicInt, AtomicObject, AtomicUInt64
+
+from util import THREADS, run_threads
+
+ITERATIONS = 2000
+
+
+def atomics():
+    """Pairs of an atomic and a function computing a different value from its own."""
+    return (
+        (AtomicBool(False), lambda value: not value),
+        (AtomicInt(1), lambda value: value + 1),
+        (AtomicUInt64(1), lambda value: value + 3),
+        (AtomicFloat(1.0), lambda value: value + 0.5),
+        (AtomicObject((1,)), lambda value: (value[0] + 1,)),
+    )
+
+
+class UpdateError(Exception):
+    pass
+
+
+class FetchUpdateTest(unittest.TestCase):
+    def test_returns_the_previous_or_the_updated_value(self):
+        atomic = AtomicInt(1)
+        self.assertEqual(atomic.fetch_update(lambda value: value + 1), 1)
+        self.assertEqual(atomic.update_and_get(lambda value: value + 1), 3)
+        self.assertEqual(atomic.load(), 3)
+
+    def test_exceptions_leave_the_value_unchanged(self):
+        def fail(value):
+            raise UpdateError(value)
+
+        for atomic, _ in atomics():
+            value = atomic.load()
+            for update in (atomic.fetch_update, atomic.update_and_get):
+                with self.assertRaises(UpdateError) as cm:
+                    update(fail)
+                self.assertEqual(cm.exception.args, (value,))
+                self.assertEqual(atomic.load(), value)
+
+    def test_abort_leaves_the_value_unchanged(self):
+        for atomic, _ in atomics():
+            value = atomic.load()
+            self.assertEqual(atomic.fetch_update(lambda value: ABORT), value)
+            self.assertEqual(atomic.update_and_get(lambda value: ABORT), value)
+            self.assertEqual(atomic.load(), value)
+
+    def test_applies_every_update_once(self):
+        for atomic, step in atomics():
+            if isinstance(atomic, AtomicBool):
+                continue
+            expected = atomic.load()
+            for _ in range(THREADS * ITERATIONS):
+                expected = step(expected)
+
+            def run(i):
+                for _ in range(ITERATIONS):
+                    atomic.fetch_update(step)
+
+            run_threads(run)
+            self.assertEqual(atomic.load(), expected)
+
+    def test_concurrent_flips_cancel_out(self):
+        atomic = AtomicBool(False)
+
+        def run(i):
+            for _ in range(ITERATIONS):
+                atomic.update_and_get(lambda value: not value)
+
+        run_threads(run)
+        self.assertFalse(atomic.load())
+
+
+if __name__ == "__main__":
+    unittest.main()
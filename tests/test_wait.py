@@ -0,0 +1,61 @@
+import math
+import threading
+import time
+import unittest
+
+from haxe_atomic import AtomicBool, AtomicInt, AtomicUInt64, ConcurrentQueue, Semaphore
+
+from util import run_threads
+
+
+class WaitTest(unittest.TestCase):
+    def test_times_out(self):
+        for atomic, expected in ((AtomicInt(0), 0), (AtomicBool(False), False)):
+            start = time.monotonic()
+            self.assertFalse(atomic.wait(expected, timeout=0.05))
+            self.assertGreaterEqual(time.monotonic() - start, 0.04)
+
+    def test_returns_immediately_if_changed(self):
+        for atomic, expected in ((AtomicInt(1), 0), (AtomicBool(True), False)):
+            start = time.monotonic()
+            self.assertTrue(atomic.wait(expected, timeout=5))
+            self.assertTrue(atomic.wait(expected))
+            self.assertLess(time.monotonic() - start, 1)
+
+    def test_infinite_timeouts_never_expire(self):
+        for timeout in (math.inf, 1e300):
+            self.assertTrue(AtomicInt(1).wait(0, timeout=timeout))
+            q = ConcurrentQueue()
+            q.push(1)
+            self.assertEqual(q.pop(timeout=timeout), 1)
+            self.assertTrue(Semaphore(1).try_acquire(timeout=timeout))
+        for timeout in (-1.0, math.nan):
+            with self.assertRaises(ValueError):
+                AtomicInt(1).wait(0, timeout=timeout)
+
+    def test_store_and_notify_all_wake_waiters(self):
+        atomic = AtomicUInt64(0)
+        waiting = threading.Barrier(4)
+
+        def run(i):
+            if i == 0:
+                waiting.wait()
+                time.sleep(0.05)
+                atomic.store(1)
+                atomic.notify_all()
+            else:
+                waiting.wait()
+                self.assertTrue(atomic.wait(0, timeout=5))
+
+        run_threads(run, count=4)
+
+    def test_notify_one_wakes_a_waiter(self):
+        atomic = AtomicInt(0)
+        notifier = threading.Timer(0.05, lambda: (atomic.store(1), atomic.notify_one()))
+        notifier.start()
+        self.assertTrue(atomic.wait(0, timeout=5))
+        notifier.join()
+
+
+if __name__ == "__main__":
+    unittest.main()
@@ -0,0 +1,92 @@
+import asyncio
+import gc
+import threading
+import unittest
+
+from haxe_atomic import AtomicInt, AtomicObject, Ordering
+
+from util import Tracked
+
+
+class Never(Tracked):
+    def __call__(self, value):
+        return False
+
+
+def run(coro):
+    loop = asyncio.new_event_loop()
+    try:
+        return loop.run_until_complete(coro)
+    finally:
+        loop.close()
+
+
+class WaitTest(unittest.TestCase):
+    def tearDown(self):
+        gc.collect()
+        self.assertEqual(Tracked.live(), 0)
+
+    def test_write_wakes_waiter(self):
+        atomic = AtomicInt(0)
+
+        async def main():
+            waiter = asyncio.ensure_future(atomic.wait_for_change(0))
+            await asyncio.sleep(0)
+            self.assertFalse(waiter.done())
+            atomic.store(1)
+            return await asyncio.wait_for(waiter, 5)
+
+        self.assertEqual(run(main()), 1)
+
+    def test_relaxed_store_from_another_thread_wakes_waiter(self):
+        async def main():
+            for _ in range(200):
+                atomic = AtomicInt(0)
+                start = threading.Event()
+
+                def store():
+                    start.wait()
+                    atomic.store(1, ordering=Ordering.Relaxed)
+
+                writer = threading.Thread(target=store)
+                writer.start()
+                # The store races with the waiter registering
+                start.set()
+                try:
+                    self.assertEqual(await asyncio.wait_for(atomic.wait_for_change(0), 5), 1)
+                finally:
+                    writer.join()
+
+        run(main())
+
+    def test_until_predicate_holds(self):
+        atomic = AtomicInt(0)
+
+        async def main():
+            waiter = asyncio.ensure_future(atomic.wait_until(lambda value: value >= 3))
+            for i in range(1, 4):
+                await asyncio.sleep(0)
+                atomic.store(i)
+            return await asyncio.wait_for(waiter, 5)
+
+        self.assertEqual(run(main()), 3)
+
+    def test_cancelled_waiters_are_released(self):
+        atomic = AtomicObject(None)
+
+        async def main():
+            for _ in range(1000):
+                atomic.wait_until(Never()).cancel()
+            for _ in range(10):
+                with self.assertRaises(asyncio.TimeoutError):
+                    await asyncio.wait_for(atomic.wait_until(Never()), 0.001)
+            # Let the done callbacks of the cancelled futures run
+            await asyncio.sleep(0)
+            # Without writing to the atomic
+            self.assertEqual(Tracked.live(), 0)
+
+        run(main())
+
+
+if __name__ == "__main__":
+    unittest.main()
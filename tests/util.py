@@ -0,0 +1,50 @@
+import threading
+
+THREADS = 8
+
+
+def run_threads(target, count=THREADS):
+    """Run `target(i)` on `count` threads started together, re-raising the first error."""
+    barrier = threading.Barrier(count)
+    errors = []
+
+    def run(i):
+        try:
+            barrier.wait()
+            target(i)
+        except BaseException as err:
+            errors.append(err)
+
+    threads = [threading.Thread(target=run, args=(i,)) for i in range(count)]
+    for thread in threads:
+        thread.start()
+    for thread in threads:
+        thread.join()
+    if errors:
+        raise errors[0]
+
+
+class Tracked:
+    """An object that counts live instances and detects use after finalization."""
+
+    _lock = threading.Lock()
+    _live = 0
+
+    def __init__(self, value=None):
+        self.value = value
+        self.finalized = False
+        with Tracked._lock:
+            Tracked._live += 1
+
+    def __del__(self):
+        self.finalized = True
+        with Tracked._lock:
+            Tracked._live -= 1
+
+    def check(self):
+        assert not self.finalized, "object used after it was finalized"
+        return self
+
+    @staticmethod
+    def live():
+        return Tracked._live